@@ -0,0 +1,220 @@
+use crate::pages::escape;
+
+/// A piece of text with ANSI escape sequences, split at them.
+enum Segment<'a> {
+    /// Text to show.
+    Text(&'a str),
+    /// A sequence setting how the text after it looks, like `\x1b[1;31m`, with its parameters.
+    Style(Vec<u16>),
+    /// Any other sequence, like one moving the cursor or a hyperlink.
+    Other,
+}
+
+/// How the text after the style sequences seen so far looks.
+#[derive(Clone, Copy, Default, PartialEq)]
+struct Style {
+    bold: bool,
+    underline: bool,
+    /// One of the 16 basic colors, the last 8 being their bright variants.
+    color: Option<u16>,
+}
+
+impl Style {
+    /// Applies the parameters of a style sequence.
+    ///
+    /// Colors outside of the basic 16, like those of the 256 and true color palettes, are shown
+    /// in the default color.
+    ///
+    /// Arguments:
+    /// * `params` - The sequence's parameters, like `[1, 31]`.
+    fn apply(&mut self, params: &[u16]) {
+        if params.is_empty() {
+            *self = Self::default();
+        }
+
+        let mut params = params.iter().copied();
+
+        while let Some(param) = params.next() {
+            match param {
+                0 => *self = Self::default(),
+                1 => self.bold = true,
+                4 => self.underline = true,
+                22 => self.bold = false,
+                24 => self.underline = false,
+                30..=37 => self.color = Some(param - 30),
+                90..=97 => self.color = Some(param - 90 + 8),
+                39 => self.color = None,
+                38 => match params.next() {
+                    Some(5) => self.color = params.next().filter(|color| *color < 16),
+                    Some(2) => {
+                        params.nth(2);
+                        self.color = None;
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+    }
+
+    /// The classes of a `<span>` showing text in this style.
+    ///
+    /// Returns:
+    /// [`String`] -> The classes, separated by spaces, or nothing for the default style.
+    fn classes(&self) -> String {
+        let mut classes = vec![];
+
+        if self.bold {
+            classes.push("ansi-bold".to_string());
+        }
+
+        if self.underline {
+            classes.push("ansi-underline".to_string());
+        }
+
+        if let Some(color) = self.color {
+            classes.push(format!("ansi-{color}"));
+        }
+
+        classes.join(" ")
+    }
+}
+
+/// Splits text at its ANSI escape sequences.
+///
+/// Arguments:
+/// * `text` - The text.
+///
+/// Returns:
+/// [`Vec<Segment>`] -> The text and the sequences, in order.
+fn segments(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = vec![];
+    let mut rest = text;
+
+    while let Some(start) = rest.find('\x1b') {
+        if start > 0 {
+            segments.push(Segment::Text(&rest[..start]));
+        }
+
+        let sequence = &rest[start + 1..];
+
+        let length = match sequence.as_bytes().first() {
+            // Control sequences end at their first byte in `@` through `~`.
+            Some(b'[') => {
+                let end = sequence[1..]
+                    .bytes()
+                    .position(|byte| (0x40..=0x7e).contains(&byte))
+                    .map(|end| end + 2);
+
+                // A sequence cut off at the end of the text is dropped.
+                let Some(end) = end else {
+                    rest = "";
+                    break;
+                };
+
+                if sequence[..end].ends_with('m') {
+                    let params = sequence[1..end - 1]
+                        .split(';')
+                        .filter(|param| !param.is_empty())
+                        .map(|param| param.parse().unwrap_or(0))
+                        .collect();
+
+                    segments.push(Segment::Style(params));
+                } else {
+                    segments.push(Segment::Other);
+                }
+
+                end
+            }
+            // Operating system commands, like hyperlinks, end at a bell or at `ESC \`.
+            Some(b']') => {
+                segments.push(Segment::Other);
+
+                match sequence.find(['\x07', '\x1b']) {
+                    Some(end) if sequence[end..].starts_with("\x1b\\") => end + 2,
+                    Some(end) => end + 1,
+                    None => sequence.len(),
+                }
+            }
+            Some(_) => {
+                segments.push(Segment::Other);
+                sequence.chars().next().map_or(0, char::len_utf8)
+            }
+            None => 0,
+        };
+
+        rest = &sequence[length..];
+    }
+
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+
+    segments
+}
+
+/// Removes the ANSI escape sequences of text, like the colors of rendered diagnostics.
+///
+/// Arguments:
+/// * `text` - The text.
+///
+/// Returns:
+/// [`String`] -> The text without them.
+pub fn strip(text: &str) -> String {
+    segments(text)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Text(text) => Some(text),
+            Segment::Style(_) | Segment::Other => None,
+        })
+        .collect()
+}
+
+/// Renders text with ANSI escape sequences to HTML, turning their colors into `<span>`s with
+/// `ansi-*` classes and escaping the rest.
+///
+/// Sequences other than those setting colors, boldness, and underlines are dropped.
+///
+/// Arguments:
+/// * `text` - The text.
+///
+/// Returns:
+/// [`String`] -> The HTML.
+pub fn to_html(text: &str) -> String {
+    let mut html = String::with_capacity(text.len());
+    let mut style = Style::default();
+    let mut open = false;
+
+    for segment in segments(text) {
+        match segment {
+            Segment::Text(text) => html.push_str(&escape(text)),
+            Segment::Style(params) => {
+                let previous = style;
+                style.apply(&params);
+
+                if style == previous {
+                    continue;
+                }
+
+                if open {
+                    html.push_str("</span>");
+                    open = false;
+                }
+
+                let classes = style.classes();
+
+                if !classes.is_empty() {
+                    html.push_str(&format!(r#"<span class="{classes}">"#));
+                    open = true;
+                }
+            }
+            Segment::Other => {}
+        }
+    }
+
+    if open {
+        html.push_str("</span>");
+    }
+
+    html
+}
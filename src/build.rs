@@ -0,0 +1,1169 @@
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Output, Stdio};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use cargo_metadata::Target;
+use serde_json::{Value, json};
+use thiserror::Error;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use tokio::fs::OpenOptions;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{Semaphore, broadcast, mpsc, oneshot, watch};
+
+use crate::follow::EditFollower;
+use crate::reload::Reloader;
+use crate::scope::TargetScope;
+use crate::snapshot::Snapshots;
+use crate::spinner::Spinner;
+use crate::{ansi, events, list};
+
+/// The most changed paths a `build_started` event lists, so that tools rewriting many files don't
+/// make it huge.
+const REPORTED_CHANGES: usize = 50;
+
+/// The state of the documentation's builds, shared between the watcher and the server.
+#[derive(Clone, Default)]
+pub struct BuildState {
+    /// Why the last build failed, if it did.
+    pub failure: Option<BuildFailure>,
+    /// Whether a build succeeded since the server started, so that there's documentation to serve.
+    pub ready: bool,
+    /// The outcome of the last build of every workspace member, if they're documented on their
+    /// own.
+    pub members: Vec<MemberBuild>,
+}
+
+/// The outcome of documenting a workspace member on its own.
+#[derive(Clone, Debug)]
+pub struct MemberBuild {
+    /// The name of the member.
+    pub name: String,
+    /// How long after `cargo doc` started it was documented, or failed to be.
+    pub duration: Duration,
+    /// Why it failed to be documented, if it did.
+    pub failure: Option<BuildFailure>,
+}
+
+/// Why a build failed.
+#[derive(Clone, Debug, Error)]
+#[error("`{command}` failed!")]
+pub struct BuildFailure {
+    /// The command of the build that failed, like `cargo doc`.
+    pub command: String,
+    /// The output of the command that failed.
+    pub output: String,
+    /// The output with the colors of cargo's diagnostics, as ANSI escape sequences, if it has
+    /// them, like for showing them in color.
+    pub ansi_output: Option<String>,
+    /// A hint on how to fix the failure, if there's one.
+    pub hint: Option<String>,
+}
+
+/// What triggered a build.
+pub enum BuildTrigger<'a> {
+    /// The server starting.
+    Startup,
+    /// Changes to the sources, with the paths that changed.
+    Changes(&'a [PathBuf]),
+    /// Changes only to the comments of the sources, like editing doc comments, with the paths
+    /// that changed.
+    Comments(&'a [PathBuf]),
+    /// A request through a [`RebuildHandle`].
+    Requested,
+}
+
+impl<'a> BuildTrigger<'a> {
+    /// The name of the trigger, as written to the build log and passed to hooks.
+    ///
+    /// Returns:
+    /// [`&str`] -> `startup`, `change`, or `request`.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Startup => "startup",
+            Self::Changes(_) | Self::Comments(_) => "change",
+            Self::Requested => "request",
+        }
+    }
+
+    /// The paths whose changes triggered the build.
+    ///
+    /// Returns:
+    /// [`&[PathBuf]`] -> The paths, relative to the crate's root, or none if it wasn't triggered by
+    /// changes.
+    fn changes(&self) -> &'a [PathBuf] {
+        match self {
+            Self::Changes(changes) | Self::Comments(changes) => changes,
+            Self::Startup | Self::Requested => &[],
+        }
+    }
+}
+
+/// An event of a build, received through [`DocsServer::subscribe()`](crate::DocsServer::subscribe).
+#[derive(Clone, Debug)]
+pub enum BuildEvent {
+    /// A build started.
+    Started,
+    /// A build succeeded.
+    Finished {
+        /// How long the build took.
+        duration: Duration,
+        /// How many warnings the build emitted.
+        warnings: usize,
+    },
+    /// A build failed.
+    Failed {
+        /// The command that failed, like `cargo doc`.
+        command: String,
+        /// The output of the command that failed.
+        stderr: String,
+    },
+}
+
+/// A request for a rebuild, answered with its outcome once the rebuild finishes.
+pub type RebuildRequest = oneshot::Sender<Result<(), BuildFailure>>;
+
+/// Forces rebuilds of the documentation from outside of the server, like after generating code.
+///
+/// Handles are cheap to clone and can be sent across tasks. Rebuilds requested while one is waiting
+/// to start are coalesced into it. They're run one at a time alongside the ones triggered by
+/// changes to the sources, and only once the server's initial build finished.
+#[derive(Clone)]
+pub struct RebuildHandle {
+    sender: mpsc::UnboundedSender<RebuildRequest>,
+}
+
+impl RebuildHandle {
+    /// Creates a handle sending rebuild requests through a channel.
+    ///
+    /// Arguments:
+    /// * `sender` - The channel the server receives rebuild requests from.
+    ///
+    /// Returns:
+    /// [`RebuildHandle`] -> The handle.
+    pub(crate) fn new(sender: mpsc::UnboundedSender<RebuildRequest>) -> Self {
+        Self { sender }
+    }
+
+    /// Rebuilds the documentation, and waits for the rebuild to finish.
+    ///
+    /// Connected browsers reload once it does, like with any other build.
+    ///
+    /// Returns:
+    /// [`Result<(), RebuildError>`] -> Why the documentation couldn't be rebuilt, if it couldn't.
+    pub async fn rebuild(&self) -> Result<(), RebuildError> {
+        let (sender, receiver) = oneshot::channel();
+
+        self.sender
+            .send(sender)
+            .map_err(|_| RebuildError::Stopped)?;
+
+        receiver
+            .await
+            .map_err(|_| RebuildError::Stopped)?
+            .map_err(RebuildError::Failed)
+    }
+}
+
+/// Why a requested rebuild didn't succeed.
+#[derive(Debug, Error)]
+pub enum RebuildError {
+    /// The server isn't running anymore.
+    #[error("The documentation server stopped!")]
+    Stopped,
+
+    /// The rebuild ran, but failed.
+    #[error(transparent)]
+    Failed(BuildFailure),
+}
+
+/// Builds the documentation and records the outcome in the shared [`BuildState`].
+#[derive(Clone)]
+pub struct Builder {
+    /// The directory to run the build at.
+    pub root: PathBuf,
+    /// The arguments to pass to `cargo`, starting with the `doc` subcommand.
+    pub cargo_args: Vec<String>,
+    /// The name and package ID of every workspace member whose outcome is told apart from the
+    /// others' with `--workspace`, by cargo's messages. Empty unless there are many members.
+    pub members: Vec<(String, String)>,
+    /// The arguments to pass to `cargo`, starting with the `rustdoc` subcommand, to document each
+    /// dependency whose private items are displayed, with `--doc-private-deps`. They're run before
+    /// `cargo doc`, so that its pages link to theirs.
+    pub private_deps: Vec<Vec<String>>,
+    /// The arguments to pass to `cargo` without those selecting the targets to document, and the
+    /// targets of the package to narrow rebuilds triggered by changes to, if they can be.
+    pub scope: Option<(Vec<String>, TargetScope)>,
+    /// What to set `RUSTDOCFLAGS` to for `cargo doc`, if it's changed.
+    pub rustdoc_flags: Option<String>,
+    /// How long `cargo doc` can run for before it's killed, if there's a limit.
+    pub timeout: Option<Duration>,
+    /// The root of the workspace, which the paths in diagnostics are relative to.
+    pub workspace_root: PathBuf,
+    /// A shell command to run before `cargo doc`, if any.
+    pub pre_build: Option<String>,
+    /// Whether to animate a spinner on the terminal while `cargo doc` runs.
+    pub spinner: bool,
+    /// A hint shown alongside the errors of `cargo doc` when it fails, if any.
+    pub hint: Option<String>,
+    /// What to show in the terminal's title next to the outcome of every build, like the names
+    /// of the packages, if it's updated with `--terminal-status`.
+    pub terminal_status: Option<String>,
+    /// Whether to ring the terminal's bell when a build fails, with `--terminal-status`.
+    pub terminal_bell: bool,
+    /// The state to record the outcome of builds in.
+    pub state: Arc<RwLock<BuildState>>,
+    /// The reloader to notify browsers through once a build finishes.
+    pub reloader: Reloader,
+    /// Set while a build is in progress.
+    pub building: watch::Sender<bool>,
+    /// Sends the events of builds to the receivers subscribed to them.
+    pub build_events: broadcast::Sender<BuildEvent>,
+    /// A file to append a line to for every build, if any.
+    pub build_log: Option<PathBuf>,
+    /// The directory the documentation is generated at, passed to hooks.
+    pub doc_root: PathBuf,
+    /// A shell command to run after every successful build, if any.
+    pub on_success: Option<String>,
+    /// A shell command to run after every failed build, if any.
+    pub on_failure: Option<String>,
+    /// The copies of the documentation to take after every successful build, if it's served
+    /// from them.
+    pub snapshots: Option<Snapshots>,
+    /// The target directory and the documented target, whose index is looked for where it's
+    /// served from after every successful build, to warn when it's built elsewhere.
+    pub served_index: Option<(PathBuf, Target)>,
+    /// Guesses the page browsers navigate to after builds triggered by a single changed file, if
+    /// they follow edits.
+    pub follow_edits: Option<EditFollower>,
+    /// The pages the last successful build generated, to tell whether the next one added or
+    /// removed any, with `--smart-reload`.
+    pub pages: Option<Arc<Mutex<Vec<String>>>>,
+    /// Limits how many builds run at the same time as the rest of the builders sharing them, if
+    /// they're limited, like with `--feature-matrix-jobs`.
+    pub permits: Option<Arc<Semaphore>>,
+}
+
+impl Builder {
+    /// Runs the pre-build command, if any, and then `cargo doc`.
+    ///
+    /// If the pre-build command fails, `cargo doc` isn't run. Browsers are told to reload whether
+    /// the build succeeds or not, so that they show its error if it failed.
+    ///
+    /// Arguments:
+    /// * `trigger` - What triggered the build.
+    ///
+    /// Returns:
+    /// [`Result<(), BuildFailure>`] -> Why the build failed, if it did.
+    pub async fn build(&self, trigger: BuildTrigger<'_>) -> Result<(), BuildFailure> {
+        // Held until the build finishes. The semaphore is never closed.
+        let _permit = match &self.permits {
+            Some(permits) => Some(permits.acquire().await.unwrap()),
+            None => None,
+        };
+
+        let start = Instant::now();
+        let started = SystemTime::now();
+
+        // Changes to comments can't add or remove items, so they can't change what the pre-build
+        // command generates, nor which pages there are. That's only known if the last build
+        // documented everything.
+        let comments_only = matches!(trigger, BuildTrigger::Comments(_)) && {
+            let state = self.state.read().unwrap();
+            state.failure.is_none() && state.members.iter().all(|m| m.failure.is_none())
+        };
+
+        self.building.send_replace(true);
+        let changes = trigger.changes();
+        events::emit(json!({
+            "event": "build_started",
+            "trigger": trigger.name(),
+            "changed": changes
+                .iter()
+                .take(REPORTED_CHANGES)
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>(),
+            "changed_count": changes.len(),
+            "comments_only": matches!(trigger, BuildTrigger::Comments(_)),
+        }));
+        self.build_events.send(BuildEvent::Started).ok();
+
+        // Once a build failed, the rebuild has to document everything to tell that it's fixed.
+        let scoped = match (&trigger, &self.scope) {
+            (
+                BuildTrigger::Changes(changes) | BuildTrigger::Comments(changes),
+                Some((cargo_args, scope)),
+            ) if self.state.read().unwrap().failure.is_none() => {
+                scope.selection(changes).map(|selection| {
+                    log::info!(
+                        "Only documenting the changed targets, `{}`...",
+                        selection.join(" ")
+                    );
+                    [cargo_args.as_slice(), &selection].concat()
+                })
+            }
+            _ => None,
+        };
+
+        // Rebuilds narrowed to the changed targets don't need the dependencies documented again.
+        let private_deps = if scoped.is_none() {
+            self.private_deps.as_slice()
+        } else {
+            &[]
+        };
+
+        let (result, members) = match self
+            .run(
+                scoped.as_deref().unwrap_or(&self.cargo_args),
+                private_deps,
+                comments_only,
+            )
+            .await
+        {
+            Ok((warnings, members)) => (Ok(warnings), Some(members)),
+            Err(failure) => (Err(failure), None),
+        };
+        let duration = start.elapsed();
+
+        match &result {
+            Ok(_) => {
+                log::info!(
+                    "Finished compiling documentation in {:.2}s",
+                    duration.as_secs_f32()
+                );
+
+                // The index of a member that failed isn't expected to be there.
+                if members
+                    .iter()
+                    .flatten()
+                    .all(|member| member.failure.is_none())
+                {
+                    self.check_served_index(started).await;
+                }
+            }
+            Err(failure) => {
+                events::emit(json!({
+                    "event": "build_failed",
+                    "command": failure.command,
+                    "error": failure.output,
+                }));
+
+                log::error!("`{}` failed!", failure.command);
+
+                // Terminals that support it make the locations of diagnostics clickable.
+                if std::io::stderr().is_terminal() {
+                    forward(&link_locations(&failure.output), log::Level::Error);
+                } else {
+                    forward(&failure.output, log::Level::Error);
+                }
+
+                if let Some(hint) = &failure.hint {
+                    log::warn!("{hint}");
+                }
+            }
+        }
+
+        if result.is_ok()
+            && let Some(snapshots) = &self.snapshots
+            && let Err(e) = snapshots.take().await
+        {
+            log::error!("Could not copy the documentation to serve it, serving the last copy: {e}");
+        }
+
+        let mut finished = json!({
+            "event": "build_finished",
+            "success": result.is_ok(),
+            "duration_ms": duration.as_millis() as u64,
+        });
+
+        if let Some(members) = members.as_ref().filter(|members| !members.is_empty()) {
+            finished["members"] = members
+                .iter()
+                .map(|member| {
+                    json!({
+                        "name": member.name,
+                        "success": member.failure.is_none(),
+                        "duration_ms": member.duration.as_millis() as u64,
+                    })
+                })
+                .collect();
+        }
+
+        events::emit(finished);
+
+        let event = match &result {
+            Ok(warnings) => BuildEvent::Finished {
+                duration,
+                warnings: *warnings,
+            },
+            Err(failure) => BuildEvent::Failed {
+                command: failure.command.clone(),
+                stderr: failure.output.clone(),
+            },
+        };
+
+        // Nobody subscribing to the events isn't an error.
+        self.build_events.send(event).ok();
+
+        let result = result.map(|_| ());
+
+        if let Some(path) = &self.build_log {
+            log_build(path, &trigger, duration, &result).await;
+        }
+
+        if let Some(name) = &self.terminal_status {
+            terminal_status(name, result.is_ok(), self.terminal_bell);
+        }
+
+        let hook = match &result {
+            Ok(()) => &self.on_success,
+            Err(_) => &self.on_failure,
+        };
+
+        if let Some(command) = hook {
+            let mut hook = shell(command);
+
+            hook.current_dir(&self.root)
+                .env("DOCS_SERVER_DOC_DIR", &self.doc_root)
+                .env("DOCS_SERVER_BUILD_TRIGGER", trigger.name())
+                .env(
+                    "DOCS_SERVER_BUILD_DURATION_MS",
+                    duration.as_millis().to_string(),
+                );
+
+            if let Err(failure) = &result {
+                hook.env("DOCS_SERVER_FAILED_COMMAND", &failure.command);
+            }
+
+            // Run in the background, so that slow hooks like uploads don't hold up reloads.
+            tokio::spawn(run_hook(command.clone(), hook));
+        }
+
+        {
+            let mut state = self.state.write().unwrap();
+            state.failure = result.clone().err();
+            state.ready |= result.is_ok();
+
+            // A failed build leaves the members' last outcome, since its error is served instead.
+            if let Some(members) = members {
+                state.members = members;
+            }
+        }
+
+        self.building.send_replace(false);
+
+        let page = match (&trigger, &self.follow_edits) {
+            (BuildTrigger::Changes([path]) | BuildTrigger::Comments([path]), Some(follower))
+                if result.is_ok() =>
+            {
+                follower.page(path)
+            }
+            _ => None,
+        };
+
+        // Without knowing the pages that were there before, every reload is structural.
+        let structural = match (&result, &self.pages) {
+            (Ok(()), Some(_)) if comments_only => false,
+            (Ok(()), Some(pages)) => {
+                let doc_root = self.doc_root.clone();
+                let generated = tokio::task::spawn_blocking(move || crate::html_files(&doc_root))
+                    .await
+                    .unwrap_or_default();
+
+                let mut pages = pages.lock().unwrap();
+                let structural = *pages != generated;
+                *pages = generated;
+                structural
+            }
+            _ => true,
+        };
+
+        self.reloader.reload_to(page, structural);
+
+        result
+    }
+
+    /// Warns when a successful build didn't generate the documented target's index where it's
+    /// served from, like when the target directory or `--target` don't match the build's.
+    ///
+    /// Rebuilds narrowed to other targets leave the index there, so they don't warn.
+    ///
+    /// Arguments:
+    /// * `since` - When the build started, to tell where it wrote the documentation to.
+    async fn check_served_index(&self, since: SystemTime) {
+        let Some((target_directory, target)) = &self.served_index else {
+            return;
+        };
+
+        let doc_dir = crate::doc_dir(target);
+        let index = self.doc_root.join(&doc_dir).join("index.html");
+
+        if tokio::fs::try_exists(&index).await.unwrap_or(false) {
+            return;
+        }
+
+        match crate::written_docs(target_directory, &doc_dir, since).await {
+            Some(written) => log::warn!(
+                "The documentation was built to `{}`, but it's served from `{}`! The server will \
+                 likely serve nothing, so `/` serves an index of the targets. Check that \
+                 `--target` and the target directory match the build's.",
+                written.display(),
+                self.doc_root.display()
+            ),
+            None => log::warn!(
+                "The documentation was built, but `{}` doesn't exist! The server will likely serve \
+                 nothing, so `/` serves an index of the targets. Check that the target directory \
+                 and `--target` match the build's, and that `{}` is the target being documented.",
+                index.display(),
+                target.name
+            ),
+        }
+    }
+
+    /// Runs the pre-build command, if any, then `cargo rustdoc` for the dependencies whose
+    /// private items are documented, and then `cargo doc`.
+    ///
+    /// Arguments:
+    /// * `cargo_args` - The arguments to pass to `cargo`, starting with the `doc` subcommand.
+    /// * `private_deps` - The arguments to pass to `cargo rustdoc` for every dependency.
+    /// * `skip_pre_build` - Whether to only run `cargo doc`.
+    ///
+    /// Returns:
+    /// [`Result<(usize, Vec<MemberBuild>), BuildFailure>`] -> How many warnings cargo emitted and
+    /// the outcome of every member's build, if they're documented on their own, or why the build
+    /// failed.
+    async fn run(
+        &self,
+        cargo_args: &[String],
+        private_deps: &[Vec<String>],
+        skip_pre_build: bool,
+    ) -> Result<(usize, Vec<MemberBuild>), BuildFailure> {
+        if let Some(command) = self.pre_build.as_ref().filter(|_| !skip_pre_build) {
+            let output = output(shell(command).current_dir(&self.root))
+                .await
+                .map_err(|e| BuildFailure {
+                    command: command.clone(),
+                    output: e.to_string(),
+                    ansi_output: None,
+                    hint: None,
+                })?;
+
+            let mut combined = String::from_utf8_lossy(&output.stdout).into_owned();
+            combined.push_str(&String::from_utf8_lossy(&output.stderr));
+
+            if !output.status.success() {
+                return Err(BuildFailure {
+                    command: command.clone(),
+                    output: combined,
+                    ansi_output: None,
+                    hint: None,
+                });
+            }
+
+            forward(&combined, log::Level::Info);
+        }
+
+        let mut warnings = 0;
+
+        for dep_args in private_deps {
+            warnings += self.cargo(dep_args).await?;
+        }
+
+        if self.members.is_empty() {
+            return Ok((warnings + self.cargo(cargo_args).await?, vec![]));
+        }
+
+        let (member_warnings, members) = self.members(cargo_args).await?;
+        Ok((warnings + member_warnings, members))
+    }
+
+    /// Runs `cargo doc` for the workspace members, telling apart those it documented from those
+    /// it didn't by its messages.
+    ///
+    /// The build only fails if every member did. Otherwise, cargo's errors are logged along with
+    /// the members that failed, and the others are served.
+    ///
+    /// Arguments:
+    /// * `cargo_args` - The arguments to pass to `cargo`, starting with the `doc` subcommand.
+    ///
+    /// Returns:
+    /// [`Result<(usize, Vec<MemberBuild>), BuildFailure>`] -> How many warnings cargo emitted and
+    /// the outcome of every member, in their order, or why the build failed.
+    async fn members(
+        &self,
+        cargo_args: &[String],
+    ) -> Result<(usize, Vec<MemberBuild>), BuildFailure> {
+        let (command, output) = self.run_cargo(cargo_args).await?;
+
+        let lines = |messages: &[&(Duration, String)]| -> String {
+            messages
+                .iter()
+                .map(|(_, line)| line.as_str())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        let messages: Vec<_> = output.messages.iter().collect();
+        let all = lines(&messages);
+
+        let members: Vec<_> = self
+            .members
+            .iter()
+            .map(|(name, id)| {
+                let own: Vec<_> = messages
+                    .iter()
+                    .copied()
+                    .filter(|(_, line)| {
+                        serde_json::from_str::<Value>(line)
+                            .is_ok_and(|message| message["package_id"] == id.as_str())
+                    })
+                    .collect();
+
+                let is = |reason: &str, level: Option<&str>, line: &str| {
+                    serde_json::from_str::<Value>(line).is_ok_and(|message| {
+                        message["reason"] == reason
+                            && level.is_none_or(|level| message["message"]["level"] == level)
+                    })
+                };
+
+                let documented = own
+                    .iter()
+                    .find(|(_, line)| is("compiler-artifact", None, line));
+                let errors = own
+                    .iter()
+                    .any(|(_, line)| is("compiler-message", Some("error"), line));
+
+                // Members depending on one that failed aren't documented, without errors of their
+                // own, so they're told why with every error.
+                let failure =
+                    (errors || (documented.is_none() && !output.status.success())).then(|| {
+                        if errors {
+                            self.failure(&command, &lines(&own), "")
+                        } else {
+                            self.failure(&command, &all, &output.stderr)
+                        }
+                    });
+
+                MemberBuild {
+                    name: name.clone(),
+                    duration: documented
+                        .or(own.last())
+                        .map_or(output.duration, |(duration, _)| *duration),
+                    failure,
+                }
+            })
+            .collect();
+
+        let (built, failed): (Vec<_>, Vec<_>) =
+            members.iter().partition(|member| member.failure.is_none());
+
+        if !output.status.success() {
+            let failure = self.failure(&command, &all, &output.stderr);
+
+            if built.is_empty() {
+                return Err(failure);
+            }
+
+            log::error!("`{}` failed!", failure.command);
+
+            if std::io::stderr().is_terminal() {
+                forward(&link_locations(&failure.output), log::Level::Error);
+            } else {
+                forward(&failure.output, log::Level::Error);
+            }
+        }
+
+        for member in &built {
+            log::info!(
+                "Documented `{}` in {:.2}s",
+                member.name,
+                member.duration.as_secs_f32()
+            );
+        }
+
+        if !failed.is_empty() {
+            let names = |members: &[&MemberBuild]| -> Vec<_> {
+                members
+                    .iter()
+                    .map(|member| format!("`{}`", member.name))
+                    .collect()
+            };
+
+            log::warn!(
+                "The documentation of {} built, but {} didn't! Only the ones that built are \
+                 linked from `/`.",
+                list(&names(&built)),
+                list(&names(&failed))
+            );
+        }
+
+        Ok((warnings(&all), members))
+    }
+
+    /// Runs `cargo`, like `cargo doc`, killing it if it takes longer than the timeout.
+    ///
+    /// Arguments:
+    /// * `cargo_args` - The arguments to pass to `cargo`, starting with the subcommand.
+    ///
+    /// Returns:
+    /// [`Result<usize, BuildFailure>`] -> How many warnings cargo emitted, or why it failed.
+    async fn cargo(&self, cargo_args: &[String]) -> Result<usize, BuildFailure> {
+        let (command, output) = self.run_cargo(cargo_args).await?;
+
+        let messages: Vec<_> = output.messages.into_iter().map(|(_, line)| line).collect();
+        let messages = messages.join("\n");
+
+        if !output.status.success() {
+            return Err(self.failure(&command, &messages, &output.stderr));
+        }
+
+        Ok(warnings(&messages))
+    }
+
+    /// Runs `cargo`, killing it if it takes longer than the timeout, whether it succeeds or not.
+    ///
+    /// Arguments:
+    /// * `cargo_args` - The arguments to pass to `cargo`, starting with the subcommand.
+    ///
+    /// Returns:
+    /// [`Result<(String, TimedOutput), BuildFailure>`] -> The command, like `cargo doc`, and what
+    /// it wrote, or why it couldn't run.
+    async fn run_cargo(
+        &self,
+        cargo_args: &[String],
+    ) -> Result<(String, TimedOutput), BuildFailure> {
+        let command = match cargo_args.first() {
+            Some(subcommand) => format!("cargo {subcommand}"),
+            None => "cargo".to_string(),
+        };
+
+        let spinner = self
+            .spinner
+            .then(|| Spinner::start("Compiling documentation..."));
+
+        let mut cargo = Command::new("cargo");
+
+        if let Some(flags) = &self.rustdoc_flags {
+            cargo.env("RUSTDOCFLAGS", flags);
+        }
+
+        let output = timed_output(cargo.current_dir(&self.root).args(cargo_args));
+
+        // Dropping the output's future kills cargo, and the rustdoc processes it started.
+        let output = match self.timeout {
+            Some(timeout) => {
+                tokio::time::timeout(timeout, output)
+                    .await
+                    .map_err(|_| BuildFailure {
+                        command: command.clone(),
+                        output: format!(
+                            "`{command}` didn't finish within {}s, and was killed.",
+                            timeout.as_secs()
+                        ),
+                        ansi_output: None,
+                        hint: Some(
+                            "Another cargo process may be holding the lock on the build directory."
+                                .to_string(),
+                        ),
+                    })?
+            }
+            None => output.await,
+        };
+
+        let output = output.map_err(|e| BuildFailure {
+            command: command.clone(),
+            output: e.to_string(),
+            ansi_output: None,
+            hint: None,
+        })?;
+
+        drop(spinner);
+
+        Ok((command, output))
+    }
+
+    /// Describes how cargo failed, from what it wrote.
+    ///
+    /// Arguments:
+    /// * `command` - The command that failed, like `cargo doc`.
+    /// * `messages` - The JSON messages cargo wrote, one per line.
+    /// * `stderr` - What cargo wrote to its error output.
+    ///
+    /// Returns:
+    /// [`BuildFailure`] -> The failure, with the diagnostics of the messages.
+    fn failure(&self, command: &str, messages: &str, stderr: &str) -> BuildFailure {
+        let mut combined = diagnostics(messages, &self.workspace_root);
+        combined.push_str(stderr);
+
+        BuildFailure {
+            command: command.to_string(),
+            output: ansi::strip(&combined),
+            ansi_output: Some(combined),
+            hint: self.hint.clone(),
+        }
+    }
+}
+
+/// Appends a line of JSON describing a build to the build log, creating it if needed.
+///
+/// Failing to write to it is logged, but doesn't fail the build.
+///
+/// Arguments:
+/// * `path` - The path of the build log.
+/// * `trigger` - What triggered the build.
+/// * `duration` - How long the build took.
+/// * `result` - The outcome of the build.
+async fn log_build(
+    path: &Path,
+    trigger: &BuildTrigger<'_>,
+    duration: Duration,
+    result: &Result<(), BuildFailure>,
+) {
+    let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+
+    let changes = trigger.changes();
+
+    let line = json!({
+        "time": now.format(&Rfc3339).unwrap_or_default(),
+        "trigger": trigger.name(),
+        "changed": changes.iter().map(|path| path.display().to_string()).collect::<Vec<_>>(),
+        "comments_only": matches!(trigger, BuildTrigger::Comments(_)),
+        "duration_ms": duration.as_millis() as u64,
+        "success": result.is_ok(),
+        "failed_command": result.as_ref().err().map(|failure| &failure.command),
+    });
+
+    let written = async {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+
+        file.write_all(format!("{line}\n").as_bytes()).await
+    };
+
+    if let Err(e) = written.await {
+        log::error!(
+            "Could not write to the build log at `{}`: {e}",
+            path.display()
+        );
+    }
+}
+
+/// Runs a command hooked to the outcome of builds, logging its output.
+///
+/// Its failure is logged, but doesn't affect the build.
+///
+/// Arguments:
+/// * `command` - The shell command, to name it in the logs.
+/// * `hook` - The command to run.
+async fn run_hook(command: String, mut hook: Command) {
+    let output = match output(&mut hook).await {
+        Ok(output) => output,
+        Err(e) => {
+            log::error!("Could not run `{command}`: {e}");
+            return;
+        }
+    };
+
+    let mut combined = String::from_utf8_lossy(&output.stdout).into_owned();
+    combined.push_str(&String::from_utf8_lossy(&output.stderr));
+
+    if output.status.success() {
+        forward(&combined, log::Level::Info);
+    } else {
+        log::error!("`{command}` failed!");
+        forward(&combined, log::Level::Error);
+    }
+}
+
+/// Renders the diagnostics of cargo's JSON messages, pointing their locations at absolute paths.
+///
+/// Arguments:
+/// * `messages` - The JSON messages, one per line.
+/// * `root` - The directory the paths in the messages are relative to.
+///
+/// Returns:
+/// [`String`] -> The rendered diagnostics, colored with ANSI escape sequences.
+fn diagnostics(messages: &str, root: &Path) -> String {
+    let mut rendered = String::new();
+
+    for line in messages.lines() {
+        let Ok(message) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+
+        let message = &message["message"];
+
+        let Some(text) = message["rendered"].as_str() else {
+            continue;
+        };
+
+        let mut text = text.to_string();
+
+        let children = message["children"].as_array().into_iter().flatten();
+        let spans = std::iter::once(message)
+            .chain(children)
+            .flat_map(|message| message["spans"].as_array().into_iter().flatten());
+
+        for file in spans.filter_map(|span| span["file_name"].as_str()) {
+            let absolute = root.join(file);
+
+            // Both the primary location and the ones in other files, which are colored apart from
+            // their arrows.
+            for arrow in ["-->", ":::"] {
+                for separator in [" ", " \x1b[0m"] {
+                    text = text.replace(
+                        &format!("{arrow}{separator}{file}:"),
+                        &format!("{arrow}{separator}{}:", absolute.display()),
+                    );
+                }
+            }
+        }
+
+        rendered.push_str(&text);
+    }
+
+    rendered
+}
+
+/// Counts the warnings in cargo's JSON messages.
+///
+/// The summaries rustc adds after them, like `2 warnings emitted`, point at no code and aren't
+/// counted.
+///
+/// Arguments:
+/// * `messages` - The JSON messages, one per line.
+///
+/// Returns:
+/// [`usize`] -> How many warnings there are.
+fn warnings(messages: &str) -> usize {
+    messages
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|message| {
+            let message = &message["message"];
+
+            message["level"] == "warning"
+                && message["spans"]
+                    .as_array()
+                    .is_some_and(|spans| !spans.is_empty())
+        })
+        .count()
+}
+
+/// Turns the locations of rendered diagnostics, like `--> /crate/src/lib.rs:1:5`, into OSC 8
+/// hyperlinks to their files.
+///
+/// Arguments:
+/// * `output` - The rendered diagnostics.
+///
+/// Returns:
+/// [`String`] -> The diagnostics, with the locations linked.
+fn link_locations(output: &str) -> String {
+    let mut linked = String::with_capacity(output.len());
+
+    for line in output.lines() {
+        let trimmed = line.trim_start();
+
+        let location = trimmed
+            .strip_prefix("--> ")
+            .or_else(|| trimmed.strip_prefix("::: "))
+            .filter(|location| location.starts_with('/'));
+
+        match location {
+            Some(location) => {
+                let file = location.split(':').next().unwrap_or(location);
+                let prefix = &line[..line.len() - location.len()];
+
+                linked.push_str(&format!(
+                    "{prefix}\x1b]8;;file://{file}\x1b\\{location}\x1b]8;;\x1b\\"
+                ));
+            }
+            None => linked.push_str(line),
+        }
+
+        linked.push('\n');
+    }
+
+    linked
+}
+
+/// Shows the outcome of a build in the terminal's title, through the standard output.
+///
+/// Arguments:
+/// * `name` - What to show next to the outcome, like the names of the packages.
+/// * `success` - Whether the build succeeded.
+/// * `bell` - Whether to also ring the terminal's bell if it failed.
+fn terminal_status(name: &str, success: bool, bell: bool) {
+    let mark = if success { '✓' } else { '✗' };
+    let mut stdout = std::io::stdout().lock();
+
+    // Control characters in the title would end the sequence early.
+    let name: String = name.chars().filter(|c| !c.is_control()).collect();
+    write!(stdout, "\x1b]0;docs {mark} {name}\x07").ok();
+
+    if bell && !success {
+        write!(stdout, "\x07").ok();
+    }
+
+    stdout.flush().ok();
+}
+
+/// Forwards the output of a command to the logs, line by line.
+///
+/// Arguments:
+/// * `output` - The output of the command.
+/// * `level` - The level to log the lines at.
+fn forward(output: &str, level: log::Level) {
+    for line in output.lines().filter(|line| !line.trim().is_empty()) {
+        log::log!(level, "{line}");
+    }
+}
+
+/// Creates a command that runs `command` with the platform's shell.
+///
+/// Like `cargo doc`, it's killed along with the processes it started if it's still running when
+/// the server shuts down, when run with [`output`].
+///
+/// Arguments:
+/// * `command` - The shell command to run.
+///
+/// Returns:
+/// [`Command`] -> The command, ready to be configured and spawned.
+fn shell(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+
+    shell.arg(command);
+    shell
+}
+
+/// Runs a command to completion, collecting its output.
+///
+/// On Unix, the command runs in a process group of its own. If the returned future is dropped
+/// before it finishes, like when a build times out or the server stops, the whole group is killed,
+/// so that the processes it started, like those of a shell's command, don't outlive it.
+///
+/// Arguments:
+/// * `command` - The command to run.
+///
+/// Returns:
+/// [`std::io::Result<Output>`] -> What the command wrote and how it exited, or why it couldn't
+/// run.
+async fn output(command: &mut Command) -> std::io::Result<Output> {
+    let (child, mut group) = spawn(command)?;
+    let output = child.wait_with_output().await;
+
+    // The command exited, so the group's ID may not be its anymore.
+    group.0 = None;
+    output
+}
+
+/// What a command wrote, with when it wrote every line of its standard output.
+struct TimedOutput {
+    /// How the command exited.
+    status: ExitStatus,
+    /// The lines of the standard output, with how long after the command started each was
+    /// written.
+    messages: Vec<(Duration, String)>,
+    /// The error output.
+    stderr: String,
+    /// How long the command ran for.
+    duration: Duration,
+}
+
+/// Runs a command to completion like [`output`], timing the lines of its standard output as they
+/// come.
+///
+/// Arguments:
+/// * `command` - The command to run.
+///
+/// Returns:
+/// [`std::io::Result<TimedOutput>`] -> What the command wrote and how it exited, or why it
+/// couldn't run.
+async fn timed_output(command: &mut Command) -> std::io::Result<TimedOutput> {
+    let start = Instant::now();
+    let (mut child, mut group) = spawn(command)?;
+
+    let stdout = child.stdout.take().expect("The standard output is piped.");
+    let mut stderr = child.stderr.take().expect("The error output is piped.");
+
+    let messages = async {
+        let mut lines = BufReader::new(stdout).lines();
+        let mut messages = vec![];
+
+        while let Some(line) = lines.next_line().await? {
+            messages.push((start.elapsed(), line));
+        }
+
+        Ok::<_, std::io::Error>(messages)
+    };
+
+    let errors = async {
+        let mut errors = vec![];
+        stderr.read_to_end(&mut errors).await?;
+        Ok::<_, std::io::Error>(String::from_utf8_lossy(&errors).into_owned())
+    };
+
+    let (messages, stderr) = tokio::try_join!(messages, errors)?;
+    let status = child.wait().await?;
+
+    // The command exited, so the group's ID may not be its anymore.
+    group.0 = None;
+
+    Ok(TimedOutput {
+        status,
+        messages,
+        stderr,
+        duration: start.elapsed(),
+    })
+}
+
+/// Spawns a command with its output piped, in a process group of its own on Unix.
+///
+/// Arguments:
+/// * `command` - The command to spawn.
+///
+/// Returns:
+/// [`std::io::Result<(Child, ProcessGroup)>`] -> The command, and its group to kill if it's
+/// dropped before exiting, or why it couldn't be spawned.
+fn spawn(command: &mut Command) -> std::io::Result<(Child, ProcessGroup)> {
+    #[cfg(unix)]
+    command.process_group(0);
+
+    let child = command
+        .kill_on_drop(true)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let group = ProcessGroup(child.id());
+    Ok((child, group))
+}
+
+/// Kills the process group led by a process once dropped.
+struct ProcessGroup(Option<u32>);
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(id) = self.0 {
+            // SAFETY: `kill` only sends a signal. The group is the one `spawn` started the
+            // command in, which is still running.
+            unsafe { libc::kill(-(id as libc::pid_t), libc::SIGKILL) };
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+/// Tells changes confined to the comments of Rust sources, like editing doc comments, apart from
+/// those to their code.
+///
+/// A hash of the code of every source file is remembered when the server starts, and again
+/// whenever it changes, to compare the next change to.
+#[derive(Default)]
+pub struct CommentChanges {
+    /// The crate's root, which the changed paths are relative to.
+    root: PathBuf,
+    /// A hash of the code of every Rust source file seen, without its comments, by absolute path.
+    code: HashMap<PathBuf, u64>,
+}
+
+impl CommentChanges {
+    /// Remembers the code of every Rust source file in the watched paths.
+    ///
+    /// Every file is read, so huge workspaces take a while.
+    ///
+    /// Arguments:
+    /// * `root` - The crate's root, which the changed paths are relative to.
+    /// * `paths` - The watched paths.
+    ///
+    /// Returns:
+    /// [`CommentChanges`] -> The remembered code.
+    pub fn new(root: PathBuf, paths: &BTreeSet<PathBuf>) -> Self {
+        let code = paths
+            .iter()
+            .flat_map(WalkDir::new)
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file() && is_rust(entry.path()))
+            .filter_map(|entry| {
+                let source = fs::read_to_string(entry.path()).ok()?;
+                Some((entry.into_path(), hash(&code(&source))))
+            })
+            .collect();
+
+        Self { root, code }
+    }
+
+    /// Whether changes were only to the comments of Rust source files, remembering their code for
+    /// the next ones.
+    ///
+    /// Files that were created, removed, or renamed, or aren't Rust sources, like `Cargo.toml`,
+    /// count as changes to the code.
+    ///
+    /// Arguments:
+    /// * `changes` - The changed paths, relative to the crate's root, with how they changed.
+    ///
+    /// Returns:
+    /// [`bool`] -> Whether every change was to comments.
+    pub fn only_comments(&mut self, changes: &BTreeMap<PathBuf, &'static str>) -> bool {
+        let mut only_comments = true;
+
+        for (path, change) in changes {
+            let path = self.root.join(path);
+
+            if !is_rust(&path) {
+                only_comments = false;
+                continue;
+            }
+
+            let Ok(source) = fs::read_to_string(&path) else {
+                self.code.remove(&path);
+                only_comments = false;
+                continue;
+            };
+
+            let code = hash(&code(&source));
+            let previous = self.code.insert(path, code);
+
+            only_comments &= *change == "changed" && previous == Some(code);
+        }
+
+        only_comments
+    }
+}
+
+/// Whether a path is of a Rust source file.
+///
+/// Arguments:
+/// * `path` - The path.
+///
+/// Returns:
+/// [`bool`] -> Whether its extension is `rs`.
+fn is_rust(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "rs")
+}
+
+/// Hashes the code of a source file, which is all that's remembered of it.
+///
+/// Arguments:
+/// * `code` - The code, without its comments.
+///
+/// Returns:
+/// [`u64`] -> The hash.
+fn hash(code: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    code.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Removes the comments of Rust source code, doc comments included, and collapses the whitespace
+/// between what's left, so that only changes to the code tell sources apart.
+///
+/// Strings and character literals are kept as they are, even if they look like comments.
+///
+/// Arguments:
+/// * `source` - The source code.
+///
+/// Returns:
+/// [`String`] -> The code, with single spaces where there were comments or whitespace.
+fn code(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut code = String::with_capacity(source.len());
+    let mut i = 0;
+
+    // Adds a single space between tokens, wherever there was whitespace or a comment.
+    let separate = |code: &mut String| {
+        if !code.is_empty() && !code.ends_with(' ') {
+            code.push(' ');
+        }
+    };
+
+    while i < chars.len() {
+        match (chars[i], chars.get(i + 1).copied()) {
+            ('/', Some('/')) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+
+                separate(&mut code);
+            }
+            // Block comments nest.
+            ('/', Some('*')) => {
+                let mut depth = 0;
+
+                while i < chars.len() {
+                    match (chars[i], chars.get(i + 1).copied()) {
+                        ('/', Some('*')) => {
+                            depth += 1;
+                            i += 2;
+                        }
+                        ('*', Some('/')) => {
+                            depth -= 1;
+                            i += 2;
+
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => i += 1,
+                    }
+                }
+
+                separate(&mut code);
+            }
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                separate(&mut code);
+            }
+            ('"', _) => i = literal(&chars, i, &mut code),
+            // Raw strings, like `r"..."`, `r#"..."#`, and `br"..."`, end at a quote followed by as
+            // many `#` as they started with, and don't have escapes.
+            ('r', Some('"' | '#')) | ('b', Some('r')) if raw_string_start(&chars, i).is_some() => {
+                let (hashes, start) = raw_string_start(&chars, i).unwrap();
+                let mut end = start + 1;
+
+                while end < chars.len() {
+                    if chars[end] == '"'
+                        && chars[end + 1..]
+                            .iter()
+                            .take(hashes)
+                            .filter(|c| **c == '#')
+                            .count()
+                            == hashes
+                    {
+                        end += 1 + hashes;
+                        break;
+                    }
+
+                    end += 1;
+                }
+
+                let end = end.min(chars.len());
+                code.extend(&chars[i..end]);
+                i = end;
+            }
+            // Character literals, unlike lifetimes, end at a quote right after their character or
+            // escape.
+            ('\'', _) if is_char_literal(&chars, i) => i = literal(&chars, i, &mut code),
+            (c, _) => {
+                code.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    code.trim_end().to_string()
+}
+
+/// Copies a string or character literal, with its escapes.
+///
+/// Arguments:
+/// * `chars` - The source code.
+/// * `start` - Where the literal's opening quote is.
+/// * `code` - What to copy it to.
+///
+/// Returns:
+/// [`usize`] -> Where the code after the literal starts.
+fn literal(chars: &[char], start: usize, code: &mut String) -> usize {
+    let quote = chars[start];
+    let mut end = start + 1;
+
+    while end < chars.len() && chars[end] != quote {
+        end += if chars[end] == '\\' { 2 } else { 1 };
+    }
+
+    let end = (end + 1).min(chars.len());
+    code.extend(&chars[start..end]);
+    end
+}
+
+/// Whether a raw string literal starts somewhere in source code.
+///
+/// Arguments:
+/// * `chars` - The source code.
+/// * `start` - Where its `r` or `br` prefix would be.
+///
+/// Returns:
+/// [`Option<(usize, usize)>`] -> How many `#` it starts with, and where its opening quote is.
+fn raw_string_start(chars: &[char], start: usize) -> Option<(usize, usize)> {
+    let mut i = start + if chars[start] == 'b' { 2 } else { 1 };
+
+    // Identifiers ending in `r`, like `for`, aren't prefixes.
+    if start > 0 && (chars[start - 1].is_alphanumeric() || chars[start - 1] == '_') {
+        return None;
+    }
+
+    let hashes = chars[i..].iter().take_while(|c| **c == '#').count();
+    i += hashes;
+
+    (chars.get(i) == Some(&'"')).then_some((hashes, i))
+}
+
+/// Whether a quote in source code starts a character literal rather than a lifetime or a label.
+///
+/// Arguments:
+/// * `chars` - The source code.
+/// * `start` - Where the quote is.
+///
+/// Returns:
+/// [`bool`] -> Whether it's a character literal.
+fn is_char_literal(chars: &[char], start: usize) -> bool {
+    match chars.get(start + 1) {
+        Some('\\') => true,
+        Some(_) => chars.get(start + 2) == Some(&'\''),
+        None => false,
+    }
+}
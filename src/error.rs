@@ -0,0 +1,73 @@
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// Why the documentation server couldn't run.
+///
+/// Each variant exits the process with its own code, see [`DocsServerError::exit_code()`].
+#[derive(Debug, Error)]
+pub enum DocsServerError {
+    /// The arguments don't select anything to document.
+    #[error("{0}")]
+    Usage(String),
+
+    /// `cargo metadata` couldn't be run, or failed.
+    #[error("Failed to get cargo metadata: {0}")]
+    Metadata(#[from] cargo_metadata::Error),
+
+    /// The initial build failed while `--fail-fast` is set.
+    #[error("The initial build failed! Exiting because `--fail-fast` is set.")]
+    Build,
+
+    /// The server couldn't be bound to an address.
+    #[error("Could not bind to address {address}: {source}")]
+    Bind {
+        /// The address that couldn't be bound to.
+        address: SocketAddr,
+        /// Why binding failed.
+        source: io::Error,
+    },
+
+    /// The server couldn't be bound to a Unix socket.
+    #[error("Could not bind to the Unix socket `{}`: {source}", path.display())]
+    BindUnix {
+        /// The path of the socket that couldn't be bound to.
+        path: PathBuf,
+        /// Why binding failed.
+        source: io::Error,
+    },
+
+    /// The source directories couldn't be watched.
+    #[error("Failed to watch source directories: {0}")]
+    Watch(#[from] notify::Error),
+
+    /// Some other IO error, like the server failing while running.
+    #[error("{0}")]
+    Io(#[from] io::Error),
+}
+
+impl DocsServerError {
+    /// The code the process exits with because of this error.
+    ///
+    /// | Code | Cause                                           |
+    /// | ---- | ----------------------------------------------- |
+    /// | 1    | Anything else, like failing to watch sources.   |
+    /// | 2    | Bad arguments.                                  |
+    /// | 3    | `cargo metadata` failed.                        |
+    /// | 4    | The initial build failed with `--fail-fast`.    |
+    /// | 5    | An address or Unix socket couldn't be bound to. |
+    ///
+    /// Returns:
+    /// [`u8`] -> The exit code.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Usage(_) => 2,
+            Self::Metadata(_) => 3,
+            Self::Build => 4,
+            Self::Bind { .. } | Self::BindUnix { .. } => 5,
+            Self::Watch(_) | Self::Io(_) => 1,
+        }
+    }
+}
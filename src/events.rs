@@ -0,0 +1,38 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde_json::Value;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Starts writing events to the standard output.
+///
+/// Logs should be moved to the error output alongside, so that they don't get mixed with the
+/// events.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether events are written to the standard output.
+///
+/// Returns:
+/// [`bool`] -> Whether [`enable()`] was called.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Writes an event to the standard output as a line of JSON, if events are enabled.
+///
+/// See the README for the events that are emitted.
+///
+/// Arguments:
+/// * `event` - The event, as an object with an `event` field naming it.
+pub fn emit(event: Value) {
+    if !is_enabled() {
+        return;
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{event}").ok();
+    stdout.flush().ok();
+}
@@ -0,0 +1,149 @@
+use std::path::{Component, Path, PathBuf};
+
+/// The kinds of items whose pages are looked for when following an edit, in order.
+const ITEM_KINDS: [&str; 5] = ["struct", "enum", "trait", "union", "type"];
+
+/// Guesses the documentation page of the item a changed source file defines, for browsers to
+/// navigate to once the documentation is rebuilt.
+///
+/// A file is taken to be the module it's named after, like `src/server.rs` or `src/server/mod.rs`
+/// for `server`. Its page is that of an item named like it, like `server::Server`, if there's one,
+/// or otherwise the module's.
+#[derive(Clone)]
+pub struct EditFollower {
+    /// The crate's root, which the changed paths are relative to.
+    root: PathBuf,
+    /// The directory the documentation is generated at.
+    doc_root: PathBuf,
+    /// The root source file of every documented target, with the directory it's documented at.
+    targets: Vec<(PathBuf, String)>,
+}
+
+impl EditFollower {
+    /// Creates a follower for the documented targets.
+    ///
+    /// Arguments:
+    /// * `root` - The crate's root, which the changed paths are relative to.
+    /// * `doc_root` - The directory the documentation is generated at.
+    /// * `targets` - The root source file of every documented target, with the directory it's
+    ///   documented at.
+    ///
+    /// Returns:
+    /// [`EditFollower`] -> The follower.
+    pub fn new(root: PathBuf, doc_root: PathBuf, targets: Vec<(PathBuf, String)>) -> Self {
+        let mut targets: Vec<_> = targets
+            .into_iter()
+            .map(|(src, dir)| (src.canonicalize().unwrap_or(src), dir))
+            .collect();
+
+        // Targets with their sources nested deeper, like binaries in `src/bin/`, own their files
+        // over the ones around them.
+        targets.sort_by_key(|(src, _)| std::cmp::Reverse(src.components().count()));
+
+        Self {
+            root,
+            doc_root,
+            targets,
+        }
+    }
+
+    /// Guesses the page of the item a source file defines.
+    ///
+    /// Only pages that exist are returned, so it has to be called once the documentation is built.
+    ///
+    /// Arguments:
+    /// * `path` - The changed file, relative to the crate's root.
+    ///
+    /// Returns:
+    /// [`Option<String>`] -> The page, relative to the documentation's root, or [`None`] if there's
+    /// no guess.
+    pub fn page(&self, path: &Path) -> Option<String> {
+        if path.extension()? != "rs" {
+            return None;
+        }
+
+        let path = self.root.join(path);
+
+        for (src, doc_dir) in &self.targets {
+            if path == *src {
+                return self.existing(&[doc_dir.clone(), "index.html".to_string()]);
+            }
+
+            let Some(relative) = src.parent().and_then(|dir| path.strip_prefix(dir).ok()) else {
+                continue;
+            };
+
+            let mut modules = vec![doc_dir.clone()];
+
+            for component in relative.with_extension("").components() {
+                match component {
+                    Component::Normal(name) => modules.push(name.to_str()?.to_string()),
+                    _ => return None,
+                }
+            }
+
+            if modules.last().is_some_and(|name| name == "mod") {
+                modules.pop();
+            }
+
+            if modules.len() == 1 {
+                return self.existing(&[doc_dir.clone(), "index.html".to_string()]);
+            }
+
+            let item = pascal_case(modules.last().unwrap());
+
+            // Items of private modules are documented where they're re-exported, usually closer
+            // to the crate's root.
+            let in_module = |depth: usize| {
+                ITEM_KINDS.iter().find_map(|kind| {
+                    let mut page = modules[..depth].to_vec();
+                    page.push(format!("{kind}.{item}.html"));
+                    self.existing(&page)
+                })
+            };
+
+            return in_module(modules.len())
+                .or_else(|| {
+                    let mut page = modules.clone();
+                    page.push("index.html".to_string());
+                    self.existing(&page)
+                })
+                .or_else(|| (1..modules.len()).rev().find_map(in_module));
+        }
+
+        None
+    }
+
+    /// Joins the components of a page's path, if the page exists.
+    ///
+    /// Arguments:
+    /// * `components` - The components, relative to the documentation's root.
+    ///
+    /// Returns:
+    /// [`Option<String>`] -> The page, or [`None`] if it doesn't exist.
+    fn existing(&self, components: &[String]) -> Option<String> {
+        let page = components.join("/");
+        self.doc_root.join(&page).is_file().then_some(page)
+    }
+}
+
+/// Converts a module's name to the name an item defined by it would have, like `http_server` to
+/// `HttpServer`.
+///
+/// Arguments:
+/// * `name` - The module's name.
+///
+/// Returns:
+/// [`String`] -> The item's name.
+fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect()
+}
@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use axum::extract::{OriginalUri, State};
+use axum::http::{StatusCode, Uri};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag, TagEnd};
+use walkdir::WalkDir;
+
+use crate::pages;
+
+/// The path the guide is served at.
+pub const PATH: &str = "/guide";
+
+/// A Markdown page rendered to HTML.
+pub struct GuidePage {
+    /// The page's title, taken from its first heading or its file's name.
+    pub title: String,
+    /// The rendered HTML of the page's content.
+    pub content: String,
+}
+
+/// The Markdown files of a directory, rendered to HTML pages and served under [`PATH`].
+#[derive(Clone)]
+pub struct Guide {
+    /// The directory the Markdown files are at.
+    dir: PathBuf,
+    /// The rendered pages, by their path relative to the guide, like `intro/setup.html`.
+    pages: Arc<RwLock<BTreeMap<String, GuidePage>>>,
+}
+
+impl Guide {
+    /// Creates a guide for the Markdown files at a directory, rendering them.
+    ///
+    /// Arguments:
+    /// * `dir` - The directory the Markdown files are at.
+    ///
+    /// Returns:
+    /// [`Guide`] -> The rendered guide.
+    pub fn new(dir: PathBuf) -> Self {
+        let guide = Self {
+            dir,
+            pages: Default::default(),
+        };
+
+        guide.render();
+        guide
+    }
+
+    /// The directory the Markdown files are at.
+    ///
+    /// Returns:
+    /// [`&Path`] -> The directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Renders every Markdown file in the directory again, replacing the previous pages.
+    ///
+    /// Files that can't be read are logged and left out.
+    pub fn render(&self) {
+        let mut pages = BTreeMap::new();
+
+        for entry in WalkDir::new(&self.dir).sort_by_file_name() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    log::error!("Could not read the guide's directory: {e}");
+                    continue;
+                }
+            };
+
+            let path = entry.path();
+
+            if !entry.file_type().is_file() || path.extension().is_none_or(|ext| ext != "md") {
+                continue;
+            }
+
+            let markdown = match std::fs::read_to_string(path) {
+                Ok(markdown) => markdown,
+                Err(e) => {
+                    log::error!("Could not read `{}`: {e}", path.display());
+                    continue;
+                }
+            };
+
+            let relative_path = path.strip_prefix(&self.dir).unwrap_or(path);
+
+            let url: Vec<_> = relative_path
+                .with_extension("html")
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect();
+
+            let (title, content) = render_markdown(&markdown);
+
+            let title = title.unwrap_or_else(|| {
+                relative_path
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+            });
+
+            pages.insert(url.join("/"), GuidePage { title, content });
+        }
+
+        *self.pages.write().unwrap() = pages;
+    }
+}
+
+/// Serves a rendered page of the guide, or its index.
+///
+/// Used as the fallback for files that aren't in the guide's directory, so that `intro.md` is
+/// served rendered at `intro.html`.
+///
+/// Arguments:
+/// * `guide` - The guide.
+/// * `original_uri` - The URI of the request before the guide's path was stripped from it.
+/// * `uri` - The URI of the request, relative to the guide.
+///
+/// Returns:
+/// [`Response`] -> The page, a redirect to the guide's index, or a 404 response.
+pub async fn page(
+    State(guide): State<Guide>,
+    OriginalUri(original_uri): OriginalUri,
+    uri: Uri,
+) -> Response {
+    // Pages link each other relatively, so the index has to be served under a trailing slash.
+    if original_uri.path() == PATH {
+        return Redirect::permanent(&format!("{PATH}/")).into_response();
+    }
+
+    let pages = guide.pages.read().unwrap();
+    let path = uri.path().trim_start_matches('/');
+
+    if path.is_empty() {
+        return Html(pages::guide_index(&pages)).into_response();
+    }
+
+    match pages.get(path) {
+        Some(page) => Html(pages::guide_page(page, path.matches('/').count())).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Renders Markdown to HTML.
+///
+/// Relative links to other Markdown files are pointed at their rendered pages.
+///
+/// Arguments:
+/// * `markdown` - The Markdown to render.
+///
+/// Returns:
+/// [`(Option<String>, String)`] -> The text of the first heading, if any, and the rendered HTML.
+fn render_markdown(markdown: &str) -> (Option<String>, String) {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_HEADING_ATTRIBUTES;
+
+    let mut title: Option<String> = None;
+    let mut in_title = false;
+
+    let parser = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Start(Tag::Heading { .. }) if title.is_none() => {
+            in_title = true;
+            title = Some(String::new());
+            event
+        }
+        Event::End(TagEnd::Heading(_)) => {
+            in_title = false;
+            event
+        }
+        Event::Text(ref text) | Event::Code(ref text) if in_title => {
+            title.as_mut().unwrap().push_str(text);
+            event
+        }
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title: link_title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: markdown_link(dest_url),
+            title: link_title,
+            id,
+        }),
+        event => event,
+    });
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, parser);
+
+    (title.filter(|title| !title.is_empty()), html)
+}
+
+/// Points a relative link to a Markdown file at its rendered page.
+///
+/// Arguments:
+/// * `url` - The link's destination.
+///
+/// Returns:
+/// [`CowStr`] -> The link's destination, with `.md` replaced by `.html` if it's to a Markdown file.
+fn markdown_link(url: CowStr) -> CowStr {
+    if url.contains("://") || url.starts_with('/') || url.starts_with("mailto:") {
+        return url;
+    }
+
+    let (path, fragment) = match url.find('#') {
+        Some(i) => url.split_at(i),
+        None => (&*url, ""),
+    };
+
+    match path.strip_suffix(".md") {
+        Some(path) => format!("{path}.html{fragment}").into(),
+        None => url,
+    }
+}
@@ -0,0 +1,150 @@
+use std::net::{IpAddr, SocketAddr, SocketAddrV6};
+
+use crate::{DocsServerError, list};
+
+/// An address of a network interface.
+struct InterfaceAddress {
+    /// The name of the interface, like `eth0`.
+    name: String,
+    /// The address, if it's an IP one, with the interface's index for IPv6 link-local addresses.
+    ip: Option<(IpAddr, u32)>,
+}
+
+/// Replaces the IP of an address to bind to with the current one of a network interface, keeping
+/// its port.
+///
+/// IPv4 addresses are replaced with the interface's IPv4 address, and IPv6 ones with its IPv6
+/// address, preferring those reachable from beyond the local link.
+///
+/// Arguments:
+/// * `name` - The name of the interface, like `eth0` or `en0`.
+/// * `address` - The address to bind to.
+///
+/// Returns:
+/// [`Result<SocketAddr, DocsServerError>`] -> The address on the interface, or why there isn't
+/// one.
+pub fn bind_address(name: &str, address: SocketAddr) -> Result<SocketAddr, DocsServerError> {
+    let interfaces = interface_addresses()?;
+
+    let ips: Vec<_> = interfaces
+        .iter()
+        .filter(|interface| interface.name == name)
+        .collect();
+
+    if ips.is_empty() {
+        let mut names = vec![];
+
+        for interface in &interfaces {
+            let listed = format!("`{}`", interface.name);
+
+            if !names.contains(&listed) {
+                names.push(listed);
+            }
+        }
+
+        return Err(DocsServerError::Usage(format!(
+            "Unknown network interface `{name}`! The available interfaces are {}.",
+            list(&names)
+        )));
+    }
+
+    let ips = ips.iter().filter_map(|interface| interface.ip);
+
+    let ip = match address {
+        SocketAddr::V4(_) => ips
+            .filter(|(ip, _)| ip.is_ipv4())
+            .map(|(ip, _)| SocketAddr::new(ip, address.port()))
+            .next(),
+        SocketAddr::V6(_) => {
+            let mut ips: Vec<_> = ips
+                .filter_map(|(ip, scope)| match ip {
+                    IpAddr::V6(ip) => Some((ip, scope)),
+                    IpAddr::V4(_) => None,
+                })
+                .collect();
+
+            ips.sort_by_key(|(ip, _)| ip.is_unicast_link_local());
+
+            ips.first().map(|(ip, scope)| {
+                let scope = if ip.is_unicast_link_local() {
+                    *scope
+                } else {
+                    0
+                };
+                SocketAddrV6::new(*ip, address.port(), 0, scope).into()
+            })
+        }
+    };
+
+    ip.ok_or_else(|| {
+        DocsServerError::Usage(format!(
+            "The network interface `{name}` has no {} address to bind to {address} on!",
+            if address.is_ipv4() { "IPv4" } else { "IPv6" }
+        ))
+    })
+}
+
+/// Lists the addresses of every network interface.
+///
+/// Returns:
+/// [`Result<Vec<InterfaceAddress>, DocsServerError>`] -> The addresses, in the order the operating
+/// system lists them, or why they couldn't be listed.
+#[cfg(unix)]
+fn interface_addresses() -> Result<Vec<InterfaceAddress>, DocsServerError> {
+    use std::ffi::CStr;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    let mut first: *mut libc::ifaddrs = std::ptr::null_mut();
+
+    // SAFETY: `first` is only read once the list is written to it.
+    if unsafe { libc::getifaddrs(&mut first) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    let mut addresses = vec![];
+    let mut current = first;
+
+    while !current.is_null() {
+        // SAFETY: The list stays valid until it's freed below, and its entries' names are
+        // null-terminated.
+        let entry = unsafe { &*current };
+        let name = unsafe { CStr::from_ptr(entry.ifa_name) }
+            .to_string_lossy()
+            .into_owned();
+
+        // SAFETY: Addresses are as long as their family's `sockaddr` structure.
+        let ip = match unsafe { entry.ifa_addr.as_ref() }.map(|addr| addr.sa_family as i32) {
+            Some(libc::AF_INET) => {
+                let addr = unsafe { &*(entry.ifa_addr as *const libc::sockaddr_in) };
+                Some((Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr)).into(), 0))
+            }
+            Some(libc::AF_INET6) => {
+                let addr = unsafe { &*(entry.ifa_addr as *const libc::sockaddr_in6) };
+                Some((
+                    Ipv6Addr::from(addr.sin6_addr.s6_addr).into(),
+                    addr.sin6_scope_id,
+                ))
+            }
+            _ => None,
+        };
+
+        addresses.push(InterfaceAddress { name, ip });
+        current = entry.ifa_next;
+    }
+
+    // SAFETY: The list came from `getifaddrs`, and nothing points into it anymore.
+    unsafe { libc::freeifaddrs(first) };
+
+    Ok(addresses)
+}
+
+/// Lists the addresses of every network interface, which isn't supported outside of Unix.
+///
+/// Returns:
+/// [`Result<Vec<InterfaceAddress>, DocsServerError>`] -> An error.
+#[cfg(not(unix))]
+fn interface_addresses() -> Result<Vec<InterfaceAddress>, DocsServerError> {
+    Err(DocsServerError::Usage(
+        "Binding to a network interface with `--interface` is only supported on Unix!".to_string(),
+    ))
+}
@@ -0,0 +1,4326 @@
+//! A minimal live-reload HTTP server for rustdoc.
+//!
+//! This is the library behind the `docs` binary, for tools that want to run the server themselves.
+//! Configure it with [`Args`] just like on the command line, and run it with [`DocsServer`]:
+//!
+//! ```no_run
+//! use clap::Parser;
+//! use docs_server::{Args, DocsServer};
+//!
+//! # async fn example() -> Result<(), docs_server::DocsServerError> {
+//! let server = DocsServer::new(Args::parse_from(["docs", "path/to/crate", "--workspace"]));
+//! let rebuilds = server.rebuild_handle();
+//!
+//! tokio::spawn(async move {
+//!     // Regenerate some code, and then rebuild the documentation.
+//!     rebuilds.rebuild().await.ok();
+//! });
+//!
+//! server.run().await
+//! # }
+//! ```
+//!
+//! Logs are written through the [`log`] crate. [`logging::init()`] sets up the logger the binary
+//! uses, and [`logging::log_to_file()`] writes them to a file like `--log-file` does, which
+//! [`DocsServer`] doesn't open on its own.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Display;
+use std::io::{self, IsTerminal, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, SystemTime};
+
+use axum::extract::{Request, State};
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE, LOCATION, WWW_AUTHENTICATE};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::serve::ListenerExt;
+use axum::{Router, ServiceExt, routing};
+use cargo_metadata::camino::Utf8PathBuf;
+use cargo_metadata::{DependencyKind, Metadata, MetadataCommand, Package, Source, Target};
+use clap::{Parser, Subcommand, ValueEnum};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, PollWatcher, RecommendedWatcher, Watcher};
+use regex::Regex;
+use socket2::{SockRef, TcpKeepalive};
+use tokio::net::{TcpListener, TcpSocket};
+use tokio::sync::{Semaphore, broadcast, mpsc, oneshot, watch};
+use tokio::task::JoinSet;
+use tokio::{fs, time};
+use tower::{Layer, ServiceExt as _};
+use tower_http::compression::CompressionLayer;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::services::{ServeDir, ServeFile};
+use walkdir::WalkDir;
+
+use crate::build::{BuildState, BuildTrigger, Builder, RebuildRequest};
+use crate::comments::CommentChanges;
+use crate::follow::EditFollower;
+use crate::guide::Guide;
+use crate::middleware::PageOptions;
+use crate::reload::Reloader;
+use crate::scope::TargetScope;
+use crate::snapshot::Snapshots;
+use crate::temp::TempTarget;
+
+pub use crate::build::{BuildEvent, BuildFailure, RebuildError, RebuildHandle};
+pub use crate::error::DocsServerError;
+
+mod ansi;
+mod build;
+mod comments;
+mod error;
+pub mod events;
+mod follow;
+mod guide;
+mod interface;
+pub mod logging;
+mod middleware;
+mod pages;
+mod reload;
+mod scope;
+pub mod service;
+mod snapshot;
+mod spinner;
+mod temp;
+
+/// The options of the documentation server, parsed from the command line by the binary.
+///
+/// Build them with [`Args::parse_from()`] to use the same arguments the binary takes.
+#[derive(Clone, Parser)]
+#[command(version, about = "A minimal live-reload HTTP server for rustdoc.")]
+pub struct Args {
+    /// The path to the crate's root, the dir at which Cargo.toml is at, or to the Cargo.toml itself
+    #[arg(env = "DOCS_SERVER_ROOT", default_value = ".")]
+    pub root: PathBuf,
+
+    /// The path to the crate's Cargo.toml, taking precedence over the root.
+    #[arg(long, value_name = "PATH", env = "DOCS_SERVER_MANIFEST_PATH")]
+    pub manifest_path: Option<PathBuf>,
+
+    /// Serve the documentation of another crate at `/<NAME>/`, like another worktree of this one,
+    /// given like `<NAME>=<PATH>`. The root is ignored.
+    ///
+    /// Can be passed more than once to compare the documentation of many crates side by side,
+    /// with an index linking all of them at `/`. Each one is built and watched on its own, with
+    /// the rest of the options, and needs its own target directory.
+    #[arg(
+        long,
+        value_name = "NAME=PATH",
+        env = "DOCS_SERVER_VARIANT",
+        value_delimiter = ',',
+        conflicts_with = "manifest_path"
+    )]
+    pub variant: Vec<String>,
+
+    /// Serve the documentation built with a set of features, like `a,b`, at a path named after
+    /// them, to check that it renders with each combination users may enable.
+    ///
+    /// Can be passed more than once, with an empty set for the default features, and an index
+    /// listing which sets built at `/`. Each set is built to its own directory in the target
+    /// directory, and they're built one at a time unless `--feature-matrix-jobs` says otherwise.
+    #[arg(
+        long,
+        value_name = "FEATURES",
+        env = "DOCS_SERVER_FEATURE_SET",
+        value_delimiter = ';',
+        conflicts_with_all = ["variant", "features", "all_features"]
+    )]
+    pub feature_set: Vec<String>,
+
+    /// How many of the sets given with `--feature-set` can build at the same time.
+    #[arg(
+        long,
+        value_name = "N",
+        env = "DOCS_SERVER_FEATURE_MATRIX_JOBS",
+        default_value_t = 1,
+        requires = "feature_set"
+    )]
+    pub feature_matrix_jobs: usize,
+
+    /// The packages to generate and serve documentation for.
+    ///
+    /// Dependencies checked out locally, like path ones, can be given too, to browse their
+    /// documentation on its own while editing them. Names that aren't packages at all are an
+    /// error. Also see `--workspace` and `--exclude`.
+    #[arg(short, long, env = "DOCS_SERVER_PACKAGE", value_delimiter = ',')]
+    pub package: Vec<String>,
+
+    /// A file listing more packages to generate and serve documentation for, one name per line,
+    /// like when a script computes them. Pass `-` to read them from the standard input.
+    ///
+    /// Empty lines are skipped, and names that aren't packages of the workspace or of its
+    /// dependencies are reported.
+    #[arg(
+        long,
+        value_name = "PATH",
+        env = "DOCS_SERVER_PACKAGES_FROM",
+        conflicts_with = "workspace"
+    )]
+    pub packages_from: Option<PathBuf>,
+
+    /// Also generate and serve documentation for the packages in the workspace whose whole name
+    /// matches this regular expression, like `my-org-.*`.
+    #[arg(long, value_name = "REGEX", env = "DOCS_SERVER_PACKAGE_REGEX")]
+    pub package_regex: Option<String>,
+
+    /// Generate documentation for all crates in this workspace.
+    ///
+    /// Members that fail to build don't keep the others from being served, and are flagged in the
+    /// index at `/`. Also see `--exclude`.
+    #[arg(short, long, env = "DOCS_SERVER_WORKSPACE")]
+    pub workspace: bool,
+
+    /// When `--workspace` is set, packages in the workspace not to generate documentation for.
+    #[arg(short, long, env = "DOCS_SERVER_EXCLUDE", value_delimiter = ',')]
+    pub exclude: Vec<String>,
+
+    /// The addresses to bind the documentation server to.
+    ///
+    /// Can be passed more than once to serve the documentation on many addresses at once.
+    #[arg(
+        short,
+        long,
+        env = "DOCS_SERVER_BIND",
+        value_delimiter = ',',
+        default_value = "0.0.0.0:8000"
+    )]
+    pub bind: Vec<SocketAddr>,
+
+    /// Bind to the loopback address on the ports of `--bind`, like `127.0.0.1:8000`, so that the
+    /// documentation is only reachable from this machine.
+    #[arg(long, env = "DOCS_SERVER_LOCALHOST", conflicts_with = "listen_fd")]
+    pub localhost: bool,
+
+    /// A network interface to bind to at its current address, like `eth0` or `en0`, on the ports
+    /// of `--bind`. Only supported on Unix.
+    ///
+    /// IPv4 addresses of `--bind` are replaced with the interface's IPv4 address, and IPv6 ones
+    /// with its IPv6 address. It's looked up every time the server starts, so it can change, like
+    /// with DHCP.
+    #[arg(
+        long,
+        value_name = "NAME",
+        env = "DOCS_SERVER_INTERFACE",
+        conflicts_with_all = ["localhost", "listen_fd"]
+    )]
+    pub interface: Option<String>,
+
+    /// An inherited file descriptor of a listening socket to serve the documentation on, instead
+    /// of binding to `--bind`.
+    ///
+    /// Can be passed more than once. Without it, sockets passed through systemd's socket
+    /// activation are used if there are any. Only supported on Unix.
+    #[arg(
+        long,
+        value_name = "FD",
+        env = "DOCS_SERVER_LISTEN_FD",
+        value_delimiter = ','
+    )]
+    pub listen_fd: Vec<i32>,
+
+    /// A Unix socket to serve the documentation at too, like for a proxy in front of the server,
+    /// on top of the addresses of `--bind`. Only supported on Unix.
+    ///
+    /// A socket left behind at the path is replaced, and the socket is removed once the server
+    /// stops.
+    #[arg(long, value_name = "PATH", env = "DOCS_SERVER_UNIX_SOCKET")]
+    pub unix_socket: Option<PathBuf>,
+
+    /// How many incoming connections can wait to be accepted, for servers hit by bursts of them.
+    ///
+    /// Doesn't apply to sockets given with `--listen-fd`, which were already listened at.
+    #[arg(
+        long,
+        value_name = "N",
+        env = "DOCS_SERVER_TCP_BACKLOG",
+        default_value_t = 1024
+    )]
+    pub tcp_backlog: u32,
+
+    /// How long TCP connections can be idle for before they're probed, to drop those whose client
+    /// went away, in seconds. `0` doesn't probe them.
+    #[arg(
+        long,
+        value_name = "SECS",
+        env = "DOCS_SERVER_TCP_KEEPALIVE",
+        default_value_t = 60
+    )]
+    pub tcp_keepalive: u64,
+
+    /// Open the documentation server on start.
+    ///
+    /// The browser is only opened once the server accepts connections, which is checked a few
+    /// times before giving up and printing the URL instead.
+    #[arg(short, long, env = "DOCS_SERVER_OPEN")]
+    pub open: bool,
+
+    /// When `--open` is set, open the documentation again after every successful rebuild too.
+    ///
+    /// Brings the freshest page to the foreground, in a new tab, at `--open-path` if it's given.
+    #[arg(long, env = "DOCS_SERVER_OPEN_ON_REBUILD", requires = "open")]
+    pub open_on_rebuild: bool,
+
+    /// With `--open`, don't open the documentation if a browser tab still shows it from an
+    /// earlier run of the server at the same address, which reconnects shortly after it starts.
+    #[arg(long, env = "DOCS_SERVER_NO_REOPEN", requires = "open")]
+    pub no_reopen: bool,
+
+    /// With `--open`, the page to open instead of the documentation's root, relative to it, like
+    /// `fixture/struct.Server.html#method.run`.
+    ///
+    /// It's appended to the server's URL as given, `#fragment` included, so it must only be
+    /// percent-encoded once, if at all.
+    #[arg(
+        long,
+        value_name = "PATH",
+        env = "DOCS_SERVER_OPEN_PATH",
+        requires = "open"
+    )]
+    pub open_path: Option<String>,
+
+    /// Also display private modules and items.
+    #[arg(short = 'r', long, env = "DOCS_SERVER_WITH_PRIVATE")]
+    pub with_private: bool,
+
+    /// Features of the packages to enable.
+    #[arg(short = 'F', long, env = "DOCS_SERVER_FEATURES", value_delimiter = ',')]
+    pub features: Vec<String>,
+
+    /// Enable all features of the packages.
+    #[arg(long, env = "DOCS_SERVER_ALL_FEATURES")]
+    pub all_features: bool,
+
+    /// When `--all-features` is set, features not to enable.
+    ///
+    /// Features that enable them are left out too. The rest are passed to `cargo doc` by name.
+    #[arg(
+        long,
+        value_name = "NAME",
+        env = "DOCS_SERVER_SKIP_FEATURE",
+        value_delimiter = ',',
+        requires = "all_features"
+    )]
+    pub skip_feature: Vec<String>,
+
+    /// Don't enable the packages' default features.
+    #[arg(long, env = "DOCS_SERVER_NO_DEFAULT_FEATURES")]
+    pub no_default_features: bool,
+
+    /// Run cargo without accessing the network.
+    #[arg(long, env = "DOCS_SERVER_OFFLINE")]
+    pub offline: bool,
+
+    /// Require `Cargo.lock` to be up to date, and run cargo without accessing the network.
+    #[arg(long, env = "DOCS_SERVER_FROZEN")]
+    pub frozen: bool,
+
+    /// Require `Cargo.lock` to be up to date.
+    #[arg(long, env = "DOCS_SERVER_LOCKED")]
+    pub locked: bool,
+
+    /// How many jobs cargo may run in parallel while building, instead of one per CPU.
+    #[arg(short, long, value_name = "N", env = "DOCS_SERVER_JOBS")]
+    pub jobs: Option<u32>,
+
+    /// Dependencies to generate documentation for alongside the selected packages.
+    ///
+    /// Other dependencies are still not documented. Those checked out locally, like forks patched
+    /// in with `[patch]`, are watched for changes too. Those from crates.io or git are documented
+    /// from cargo's caches, which aren't watched since they're not meant to be edited.
+    #[arg(
+        long = "doc-dep",
+        value_name = "NAME",
+        env = "DOCS_SERVER_DOC_DEP",
+        value_delimiter = ','
+    )]
+    pub doc_deps: Vec<String>,
+
+    /// Also display the private modules and items of the dependencies given with `--doc-dep`.
+    ///
+    /// Each dependency is documented on its own with `cargo rustdoc`, before the selected packages,
+    /// whose private items are still only displayed with `--with-private`. Only their libraries
+    /// are documented.
+    #[arg(long, env = "DOCS_SERVER_DOC_PRIVATE_DEPS", requires = "doc_deps")]
+    pub doc_private_deps: bool,
+
+    /// A path prefix to strip from incoming requests before serving them.
+    ///
+    /// Useful behind a proxy that forwards `/<prefix>/<crate>/...` as is. No prefix is added to
+    /// the generated documentation's URLs, since rustdoc links pages relatively. Requests without
+    /// the prefix are still served.
+    #[arg(long, value_name = "PREFIX", env = "DOCS_SERVER_STRIP_PREFIX")]
+    pub strip_prefix: Option<String>,
+
+    /// Redirect requests for a path to another one, like `/crate/old/=/crate/new/`. Can be given
+    /// many times.
+    ///
+    /// Keeps links to documentation that moved working. Redirects are served before the
+    /// documentation, and are temporary unless `--permanent-redirects` is set.
+    #[arg(
+        long = "redirect",
+        value_name = "FROM=TO",
+        env = "DOCS_SERVER_REDIRECT",
+        value_delimiter = ','
+    )]
+    pub redirects: Vec<String>,
+
+    /// Answer the redirects given with `--redirect` with `301 Moved Permanently`, instead of
+    /// `302 Found`.
+    #[arg(long, env = "DOCS_SERVER_PERMANENT_REDIRECTS", requires = "redirects")]
+    pub permanent_redirects: bool,
+
+    /// A shell command to run at the crate's root before every build.
+    ///
+    /// If it fails, the documentation isn't rebuilt. Rebuilds of changes only to comments skip
+    /// it, unless the last build failed.
+    #[arg(long, value_name = "COMMAND", env = "DOCS_SERVER_PRE_BUILD")]
+    pub pre_build: Option<String>,
+
+    /// A shell command to run after every successful build, like uploading the documentation.
+    ///
+    /// It runs at the crate's root in the background, with `DOCS_SERVER_DOC_DIR` set to where the
+    /// documentation is, `DOCS_SERVER_BUILD_TRIGGER` to `startup`, `change`, or `request`, and
+    /// `DOCS_SERVER_BUILD_DURATION_MS` to how long the build took. Its failure is only logged.
+    #[arg(long, value_name = "COMMAND", env = "DOCS_SERVER_ON_SUCCESS")]
+    pub on_success: Option<String>,
+
+    /// A shell command to run after every failed build, like `--on-success`.
+    ///
+    /// `DOCS_SERVER_FAILED_COMMAND` is also set to the command that failed, like `cargo doc`.
+    #[arg(long, value_name = "COMMAND", env = "DOCS_SERVER_ON_FAILURE")]
+    pub on_failure: Option<String>,
+
+    /// How long to wait for more source changes before rebuilding, in milliseconds.
+    #[arg(
+        long,
+        value_name = "MS",
+        env = "DOCS_SERVER_DEBOUNCE",
+        default_value_t = 200
+    )]
+    pub debounce: u64,
+
+    /// Like `--debounce`, but while the changes waited on include files being created, removed,
+    /// or renamed, which usually need a rebuild right away. Defaults to `--debounce`.
+    #[arg(long, value_name = "MS", env = "DOCS_SERVER_DEBOUNCE_STRUCTURAL")]
+    pub debounce_structural: Option<u64>,
+
+    /// Like `--debounce`, but while the changes waited on only modify files, like while typing.
+    /// Defaults to `--debounce`.
+    #[arg(long, value_name = "MS", env = "DOCS_SERVER_DEBOUNCE_MODIFY")]
+    pub debounce_modify: Option<u64>,
+
+    /// The least time between the start of two rebuilds, in milliseconds.
+    ///
+    /// Unlike `--debounce`, rebuilds happen even if sources keep changing, just not more often
+    /// than this. Changes made meanwhile are rebuilt once it passes.
+    #[arg(
+        long,
+        value_name = "MS",
+        env = "DOCS_SERVER_MIN_REBUILD_INTERVAL",
+        default_value_t = 0
+    )]
+    pub min_rebuild_interval: u64,
+
+    /// After this many rebuilds in a row fail, double the least time between rebuilds with every
+    /// further failure, until one succeeds.
+    ///
+    /// Backing off starts at `--min-rebuild-interval`, or a second if that's shorter, and goes up
+    /// to `--max-rebuild-backoff`.
+    #[arg(long, value_name = "N", env = "DOCS_SERVER_REBUILD_BACKOFF")]
+    pub rebuild_backoff: Option<u32>,
+
+    /// The most time between the start of two rebuilds while backing off with `--rebuild-backoff`,
+    /// in milliseconds.
+    #[arg(
+        long,
+        value_name = "MS",
+        env = "DOCS_SERVER_MAX_REBUILD_BACKOFF",
+        default_value_t = 60000,
+        requires = "rebuild_backoff"
+    )]
+    pub max_rebuild_backoff: u64,
+
+    /// Ignore source changes for this long after the initial build, in milliseconds.
+    ///
+    /// Some platforms report changes made shortly before the server started, which would trigger
+    /// a rebuild right away.
+    #[arg(
+        long,
+        value_name = "MS",
+        env = "DOCS_SERVER_STARTUP_GRACE",
+        default_value_t = 0
+    )]
+    pub startup_grace: u64,
+
+    /// Ignore source changes until none were made for this long, in milliseconds, after the
+    /// initial build and `--startup-grace`.
+    ///
+    /// Editors opening the project often rewrite caches and touch files for a while, which would
+    /// otherwise trigger rebuilds right away.
+    #[arg(long, value_name = "MS", env = "DOCS_SERVER_WARMUP_QUIET")]
+    pub warmup_quiet: Option<u64>,
+
+    /// How to find out about changes to the sources.
+    ///
+    /// If the operating system runs out of watches for them, like on huge workspaces, they're
+    /// scanned every `--poll-interval` instead.
+    #[arg(
+        long,
+        value_enum,
+        env = "DOCS_SERVER_WATCH_MODE",
+        default_value_t = WatchMode::Events
+    )]
+    pub watch_mode: WatchMode,
+
+    /// When `--watch-mode` is `poll` or `hybrid`, how often to scan the sources for changes, in
+    /// milliseconds.
+    #[arg(
+        long,
+        value_name = "MS",
+        env = "DOCS_SERVER_POLL_INTERVAL",
+        default_value_t = 2000
+    )]
+    pub poll_interval: u64,
+
+    /// Only watch these directories of the sources, relative to the crate's root, like
+    /// `src/parser`. Changes anywhere else don't trigger rebuilds.
+    ///
+    /// Can be passed more than once. The directories must be within the watched sources.
+    #[arg(
+        long,
+        value_name = "PATH",
+        env = "DOCS_SERVER_WATCH_ONLY",
+        value_delimiter = ','
+    )]
+    pub watch_only: Vec<PathBuf>,
+
+    /// A file of patterns in the syntax of `.gitignore`, like `.docsignore`, for changes to the
+    /// files matching them not to trigger rebuilds.
+    ///
+    /// Can be passed more than once. Patterns are relative to the directory of their file.
+    /// Missing files are warned about and skipped.
+    #[arg(
+        long,
+        value_name = "PATH",
+        env = "DOCS_SERVER_IGNORE_FILE",
+        value_delimiter = ','
+    )]
+    pub ignore_file: Vec<PathBuf>,
+
+    /// Also watch the sources of the packages' path dependencies, like `path = "../lib"`, and
+    /// rebuild when they change.
+    #[arg(long, env = "DOCS_SERVER_REBUILD_ON_DEPENDENCY_SOURCE_CHANGE")]
+    pub rebuild_on_dependency_source_change: bool,
+
+    /// Don't watch the sources for changes, only building the documentation once it starts.
+    ///
+    /// Combined with `--allow-remote-rebuild`, the documentation is only rebuilt when requested.
+    #[arg(
+        long,
+        env = "DOCS_SERVER_NO_WATCH",
+        conflicts_with_all = ["watch_only", "ignore_file", "rebuild_on_dependency_source_change"]
+    )]
+    pub no_watch: bool,
+
+    /// Rebuild the documentation on `POST /__rebuild` requests, answering them once the rebuild
+    /// finishes.
+    ///
+    /// Anyone who can reach the server can trigger rebuilds, so consider passing `--localhost` or
+    /// `--rebuild-token`.
+    #[arg(long, env = "DOCS_SERVER_ALLOW_REMOTE_REBUILD")]
+    pub allow_remote_rebuild: bool,
+
+    /// With `--allow-remote-rebuild`, only rebuild on requests with an `Authorization: Bearer
+    /// <SECRET>` header, answering others with `401 Unauthorized`.
+    #[arg(
+        long,
+        value_name = "SECRET",
+        env = "DOCS_SERVER_REBUILD_TOKEN",
+        requires = "allow_remote_rebuild"
+    )]
+    pub rebuild_token: Option<String>,
+
+    /// The largest body a request can have, in bytes. Requests with larger ones are answered with
+    /// `413 Payload Too Large`.
+    ///
+    /// Nothing the server serves reads request bodies, so they're only limited to keep clients
+    /// from tying it up with huge ones.
+    #[arg(
+        long,
+        value_name = "BYTES",
+        env = "DOCS_SERVER_MAX_REQUEST_SIZE",
+        default_value_t = 16 * 1024
+    )]
+    pub max_request_size: usize,
+
+    /// Serve what the server resolved its options to at `/__debug/config`, like the paths it
+    /// watches and the `cargo doc` command it runs, to troubleshoot edits not being rebuilt.
+    ///
+    /// It reveals the paths of the crate and of the build, so it's off by default.
+    #[arg(long, env = "DOCS_SERVER_DEBUG_ENDPOINTS")]
+    pub debug_endpoints: bool,
+
+    /// The target triple to document for, instead of the host's.
+    #[arg(long, value_name = "TRIPLE", env = "DOCS_SERVER_TARGET")]
+    pub target: Option<String>,
+
+    /// The directory to write the documentation and the rest of the build's output to, instead of
+    /// cargo's target directory.
+    #[arg(
+        long,
+        value_name = "PATH",
+        env = "DOCS_SERVER_TARGET_DIR",
+        conflicts_with = "tmp_target"
+    )]
+    pub target_dir: Option<PathBuf>,
+
+    /// Build to a temporary directory, and remove it once the server stops, like for previews on
+    /// read-only filesystems.
+    ///
+    /// Nothing built before is reused, so the initial build compiles every dependency. The
+    /// directory is removed on Ctrl-C, `SIGTERM`, and panics, but not if the process is killed.
+    #[arg(long, env = "DOCS_SERVER_TMP_TARGET")]
+    pub tmp_target: bool,
+
+    /// Print the directory the documentation is generated at, accounting for `--target-dir` and
+    /// `--target`, and exit without building or serving it.
+    #[arg(
+        long,
+        env = "DOCS_SERVER_PRINT_DOC_DIR",
+        conflicts_with_all = ["tmp_target", "variant", "feature_set"]
+    )]
+    pub print_doc_dir: bool,
+
+    /// Refuse to build the documentation if the git repository the crate is in has uncommitted
+    /// changes, so that what's built matches a commit, like for published snapshots.
+    ///
+    /// Crates that aren't in a git repository are built anyway, with a warning, but ones git fails
+    /// to check for another reason, like a repository owned by another user, aren't.
+    #[arg(long, env = "DOCS_SERVER_REQUIRE_CLEAN")]
+    pub require_clean: bool,
+
+    /// Document every library, binary, and example of the packages.
+    ///
+    /// An index linking all of them is served at `/` instead of redirecting to a single one.
+    #[arg(long, env = "DOCS_SERVER_ALL_TARGETS")]
+    pub all_targets: bool,
+
+    /// Document a single example of the packages instead of their library, and redirect `/` to it.
+    #[arg(
+        long,
+        value_name = "NAME",
+        env = "DOCS_SERVER_EXAMPLE",
+        conflicts_with = "all_targets"
+    )]
+    pub example: Option<String>,
+
+    /// Remove the packages' previously generated documentation before the initial build.
+    ///
+    /// Keeps pages of items that no longer exist from being served. Only the packages' own
+    /// directories in the documentation's output are removed.
+    #[arg(long, env = "DOCS_SERVER_CLEAN")]
+    pub clean: bool,
+
+    /// Exit with an error if the initial build fails instead of serving its error.
+    #[arg(long, env = "DOCS_SERVER_FAIL_FAST")]
+    pub fail_fast: bool,
+
+    /// Fail builds that have warnings, like broken intra-doc links, by passing `-D warnings` to
+    /// rustdoc.
+    ///
+    /// Builds failing because of warnings show their error like any other failure. With
+    /// `--fail-fast`, the server exits if the initial build has any.
+    #[arg(long, env = "DOCS_SERVER_DENY_WARNINGS")]
+    pub deny_warnings: bool,
+
+    /// Kill `cargo doc` if it runs for longer than this, in seconds, failing the build. Useful
+    /// when it may wait forever on a lock held by another cargo process.
+    #[arg(long, value_name = "SECS", env = "DOCS_SERVER_BUILD_TIMEOUT")]
+    pub build_timeout: Option<u64>,
+
+    /// How often to ping browsers through the live-reload connection, in seconds.
+    ///
+    /// Keeps proxies from dropping idle connections. Set to 0 to never ping.
+    #[arg(
+        long,
+        value_name = "SECS",
+        env = "DOCS_SERVER_RELOAD_PING",
+        default_value_t = 30
+    )]
+    pub reload_ping: u64,
+
+    /// How long to wait for more builds to finish before reloading browsers, in milliseconds.
+    ///
+    /// Coalesces builds finishing in quick succession into a single reload. Set to 0 to reload on
+    /// every build.
+    #[arg(
+        long,
+        value_name = "MS",
+        env = "DOCS_SERVER_RELOAD_DEBOUNCE",
+        default_value_t = 100
+    )]
+    pub reload_debounce: u64,
+
+    /// Don't keep the scroll position of pages when they reload after a build.
+    #[arg(long, env = "DOCS_SERVER_NO_PRESERVE_SCROLL")]
+    pub no_preserve_scroll: bool,
+
+    /// When a build doesn't add or remove pages, swap the content and sidebar of the pages open in
+    /// browsers in place instead of reloading them, which is quicker on large crates.
+    ///
+    /// Pages are still reloaded in full if they can't be swapped, like after a failed build.
+    #[arg(long, env = "DOCS_SERVER_SMART_RELOAD")]
+    pub smart_reload: bool,
+
+    /// Navigate browsers to the documentation of the file that changed when they reload, instead
+    /// of reloading the page they show.
+    ///
+    /// Only applies when a single source file changed. Its page is guessed from its path, like
+    /// `struct.Server.html` or the `server` module for `src/server.rs`, and pages reload as usual
+    /// when there's no guess.
+    #[arg(long, env = "DOCS_SERVER_FOLLOW_EDITS")]
+    pub follow_edits: bool,
+
+    /// Hold requests for pages while a build is in progress for up to this long, in milliseconds.
+    ///
+    /// Keeps half-written pages from being served. Other files are served right away.
+    #[arg(long, value_name = "MS", env = "DOCS_SERVER_WAIT_FOR_BUILD")]
+    pub wait_for_build: Option<u64>,
+
+    /// Serve a copy of the documentation taken after every successful build, instead of the
+    /// directory being built to.
+    ///
+    /// Keeps pages from being served while a build writes them, at the cost of the disk space and
+    /// time to copy the documentation after builds.
+    #[arg(long, env = "DOCS_SERVER_ATOMIC_SWAP")]
+    pub atomic_swap: bool,
+
+    /// Compress responses with gzip or brotli for browsers that accept it.
+    ///
+    /// Useful when serving the documentation over a slow network. Also see `--min-compress-size`.
+    #[arg(long, env = "DOCS_SERVER_COMPRESS")]
+    pub compress: bool,
+
+    /// When `--compress` is set, the size under which responses aren't compressed, in bytes.
+    ///
+    /// Compressing tiny files wastes time and can even make them bigger. Raster images and fonts,
+    /// which are already compressed, never are, but SVGs are like any other text.
+    #[arg(
+        long,
+        value_name = "BYTES",
+        env = "DOCS_SERVER_MIN_COMPRESS_SIZE",
+        default_value_t = 1024
+    )]
+    pub min_compress_size: u16,
+
+    /// A directory of Markdown files to render and serve as a guide at `/guide/`.
+    ///
+    /// The pages are rendered again and reloaded when the files change.
+    #[arg(long, value_name = "PATH", env = "DOCS_SERVER_MARKDOWN_DIR")]
+    pub markdown_dir: Option<PathBuf>,
+
+    /// The URL the documentation is reachable at from outside, like
+    /// `https://intranet.example/docs/`, for the links that need to be absolute.
+    #[arg(long, value_name = "URL", env = "DOCS_SERVER_PUBLIC_URL")]
+    pub public_url: Option<String>,
+
+    /// Serve a sitemap of every page at `/sitemap.xml`, for search engines to crawl the
+    /// documentation with. The pages are linked under `--public-url`.
+    #[arg(long, env = "DOCS_SERVER_SITEMAP", requires = "public_url")]
+    pub sitemap: bool,
+
+    /// An HTML file to serve at `/` instead of redirecting to the documentation, like a curated
+    /// landing page.
+    ///
+    /// It's read again on every request, so it can be edited while the server runs.
+    #[arg(long, value_name = "PATH", env = "DOCS_SERVER_ROOT_PAGE")]
+    pub root_page: Option<PathBuf>,
+
+    /// A stylesheet to load on every page after rustdoc's, like to try out styles for
+    /// `--extend-css`.
+    ///
+    /// Editing it restyles the pages open in browsers in place, without rebuilding the
+    /// documentation or reloading them.
+    #[arg(long, value_name = "PATH", env = "DOCS_SERVER_CUSTOM_CSS")]
+    pub custom_css: Option<PathBuf>,
+
+    /// An image to use as the favicon of every page, instead of rustdoc's.
+    #[arg(long, value_name = "PATH", env = "DOCS_SERVER_FAVICON")]
+    pub favicon: Option<PathBuf>,
+
+    /// Text to append to the title of every page, to tell tabs of different crates apart.
+    #[arg(long, value_name = "TEXT", env = "DOCS_SERVER_TITLE_SUFFIX")]
+    pub title_suffix: Option<String>,
+
+    /// A file to append a line of JSON to for every build, with when and why it ran, how long it
+    /// took, and whether it succeeded.
+    #[arg(long, value_name = "PATH", env = "DOCS_SERVER_BUILD_LOG")]
+    pub build_log: Option<PathBuf>,
+
+    /// Log every request, with the address it came from and its response's status.
+    ///
+    /// Headers are never logged, so credentials don't end up in the logs.
+    #[arg(long, env = "DOCS_SERVER_ACCESS_LOG")]
+    pub access_log: bool,
+
+    /// When `--access-log` is set, the most characters of a request's URI to log.
+    #[arg(
+        long,
+        value_name = "CHARS",
+        env = "DOCS_SERVER_MAX_LOG_URI_LENGTH",
+        default_value_t = 256
+    )]
+    pub max_log_uri_length: usize,
+
+    /// The rustdoc theme to always open pages in, like `light`, `dark`, or `ayu`.
+    ///
+    /// Overrides the theme picked in rustdoc's settings. Custom themes passed to rustdoc with
+    /// `--theme` through `RUSTDOCFLAGS` can be used too.
+    #[arg(long, value_name = "NAME", env = "DOCS_SERVER_DEFAULT_THEME")]
+    pub default_theme: Option<String>,
+
+    /// Write build events to the standard output as lines of JSON, and logs to the error output.
+    ///
+    /// Meant for editors and other tools wrapping the server. See the README for the events.
+    #[arg(long, env = "DOCS_SERVER_EVENTS_TO_STDOUT")]
+    pub events_to_stdout: bool,
+
+    /// Only log warnings and errors, and don't animate a spinner while building.
+    #[arg(short, long, env = "DOCS_SERVER_QUIET")]
+    pub quiet: bool,
+
+    /// Show whether the last build succeeded in the terminal's title, like `docs ✓ crate`, when
+    /// the standard output is a terminal.
+    #[arg(
+        long,
+        env = "DOCS_SERVER_TERMINAL_STATUS",
+        conflicts_with = "events_to_stdout"
+    )]
+    pub terminal_status: bool,
+
+    /// With `--terminal-status`, also ring the terminal's bell when a build fails.
+    #[arg(long, env = "DOCS_SERVER_TERMINAL_BELL", requires = "terminal_status")]
+    pub terminal_bell: bool,
+
+    /// Also write the logs to a file, without colors, like for servers running unattended.
+    ///
+    /// Only the lines logged at the levels shown on the terminal are written to it. Only the
+    /// `docs` binary opens it, since tools running the server through the library set up logging
+    /// themselves.
+    #[arg(long, value_name = "PATH", env = "DOCS_SERVER_LOG_FILE")]
+    pub log_file: Option<PathBuf>,
+
+    /// What to do with the `--log-file` of an earlier run.
+    #[arg(
+        long,
+        value_enum,
+        env = "DOCS_SERVER_LOG_FILE_MODE",
+        default_value_t = LogFileMode::Append,
+        requires = "log_file"
+    )]
+    pub log_file_mode: LogFileMode,
+
+    /// What to do instead of serving the documentation, if anything.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// What the binary can do instead of serving the documentation, see [`Args::command`].
+#[derive(Clone, Debug, Subcommand)]
+pub enum Command {
+    /// Print a service file running the server with the options given before this subcommand,
+    /// from the current directory, like `docs --package my-crate generate-service systemd`.
+    GenerateService {
+        /// The service manager to write the file for.
+        #[arg(value_enum)]
+        manager: ServiceManager,
+
+        /// With systemd, also print a socket unit listening at `--bind`, which starts the server
+        /// on the first connection to it.
+        #[arg(long)]
+        socket_activation: bool,
+    },
+}
+
+/// The service managers `generate-service` writes files for, see [`Command::GenerateService`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ServiceManager {
+    /// A systemd unit, for Linux.
+    Systemd,
+    /// A launchd property list, for macOS.
+    Launchd,
+}
+
+/// How the sources are watched for changes, see [`Args::watch_mode`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum WatchMode {
+    /// Get told about changes by the operating system.
+    Events,
+    /// Scan the sources for changed modification times every `--poll-interval`, for filesystems
+    /// that don't report changes, like some network ones.
+    Poll,
+    /// Get told about changes, and also scan for the ones that weren't reported. A change found
+    /// both ways only triggers one rebuild.
+    Hybrid,
+}
+
+/// What to do with the log file of an earlier run, see [`Args::log_file_mode`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LogFileMode {
+    /// Keep its lines, and write the new ones after them.
+    Append,
+    /// Remove its lines.
+    Truncate,
+    /// Move it next to itself with `.old` appended to its name, replacing the one moved there by
+    /// the run before it.
+    Rotate,
+}
+
+/// How many build events subscribers can fall behind by before missing some.
+const BUILD_EVENTS_CAPACITY: usize = 64;
+
+/// How long browser tabs left open by an earlier run of the server have to reconnect to it before
+/// it's opened anyway, with `--no-reopen`. They try to every second.
+const REOPEN_GRACE: Duration = Duration::from_secs(3);
+
+/// How many times the address the browser is opened at is probed before giving up on it.
+const OPEN_PROBES: u32 = 10;
+
+/// How long to wait between probes of the address the browser is opened at, and for each of them
+/// to connect.
+const OPEN_PROBE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The most changed paths named when logging that they trigger a rebuild, with the rest counted.
+const LOGGED_CHANGES: usize = 5;
+
+/// A documentation server.
+///
+/// It compiles the crate's documentation and recompiles it automatically when the source code
+/// changes.
+pub struct DocsServer {
+    /// The options of the server.
+    args: Args,
+    /// Sends rebuild requests to the server, handed out by [`DocsServer::rebuild_handle()`].
+    rebuild_sender: mpsc::UnboundedSender<RebuildRequest>,
+    /// Receives the rebuild requests once the server runs.
+    rebuild_receiver: mpsc::UnboundedReceiver<RebuildRequest>,
+    /// Sends the events of builds to the receivers handed out by [`DocsServer::subscribe()`].
+    build_events: broadcast::Sender<BuildEvent>,
+}
+
+impl DocsServer {
+    /// Creates a documentation server. It doesn't do anything until it's run.
+    ///
+    /// Arguments:
+    /// * `args` - The options of the server.
+    ///
+    /// Returns:
+    /// [`DocsServer`] -> The server.
+    pub fn new(args: Args) -> Self {
+        let (rebuild_sender, rebuild_receiver) = mpsc::unbounded_channel();
+        let (build_events, _) = broadcast::channel(BUILD_EVENTS_CAPACITY);
+
+        Self {
+            args,
+            rebuild_sender,
+            rebuild_receiver,
+            build_events,
+        }
+    }
+
+    /// A handle to force rebuilds of the documentation with, see [`RebuildHandle`].
+    ///
+    /// Handles can be taken before the server runs. Rebuilds requested before the initial build
+    /// finishes happen right after it. With `--variant`, every variant is rebuilt.
+    ///
+    /// Returns:
+    /// [`RebuildHandle`] -> The handle.
+    pub fn rebuild_handle(&self) -> RebuildHandle {
+        RebuildHandle::new(self.rebuild_sender.clone())
+    }
+
+    /// Subscribes to the events of every build, like [`BuildEvent::Started`] once `cargo doc`
+    /// starts. With `--variant`, the events of every variant are received.
+    ///
+    /// Subscribe before running the server to receive the events of the initial build. Only
+    /// events sent after subscribing are received.
+    ///
+    /// The receiver is lossy: if it falls more than 64 events behind, the oldest ones are dropped,
+    /// and its next [`recv()`](broadcast::Receiver::recv) fails with
+    /// [`RecvError::Lagged`](broadcast::error::RecvError::Lagged) saying how many. Receiving again
+    /// picks up from the oldest event still kept, so lagging can be handled by carrying on, like:
+    ///
+    /// ```no_run
+    /// use tokio::sync::broadcast::error::RecvError;
+    ///
+    /// # async fn example(server: docs_server::DocsServer) {
+    /// let mut events = server.subscribe();
+    ///
+    /// tokio::spawn(async move {
+    ///     loop {
+    ///         match events.recv().await {
+    ///             Ok(event) => println!("{event:?}"),
+    ///             Err(RecvError::Lagged(skipped)) => println!("Missed {skipped} events"),
+    ///             Err(RecvError::Closed) => break,
+    ///         }
+    ///     }
+    /// });
+    ///
+    /// server.run().await.ok();
+    /// # }
+    /// ```
+    ///
+    /// Returns:
+    /// [`broadcast::Receiver<BuildEvent>`] -> The receiver of the events.
+    pub fn subscribe(&self) -> broadcast::Receiver<BuildEvent> {
+        self.build_events.subscribe()
+    }
+
+    /// Builds the documentation, watches the sources, and serves the documentation until the
+    /// server stops.
+    ///
+    /// The server shuts down gracefully on Ctrl-C, or `SIGTERM` on Unix, returning `Ok(())`.
+    ///
+    /// Once this returns, rebuilds requested through handles fail with
+    /// [`RebuildError::Stopped`].
+    ///
+    /// Returns:
+    /// [`Result<(), DocsServerError>`] -> Why the server couldn't run, if it couldn't.
+    pub async fn run(self) -> Result<(), DocsServerError> {
+        let remote_rebuilds = self
+            .args
+            .allow_remote_rebuild
+            .then(|| self.rebuild_handle());
+
+        run(
+            self.args,
+            self.rebuild_receiver,
+            remote_rebuilds,
+            self.build_events,
+        )
+        .await
+    }
+}
+
+/// Builds the documentation, watches the sources, and serves the documentation until the server
+/// stops.
+///
+/// Arguments:
+/// * `args` - The options of the server.
+/// * `rebuilds` - Receives requests to rebuild the documentation.
+/// * `remote_rebuilds` - The handle to rebuild the documentation through on requests to
+///   [`middleware::REBUILD_PATH`], if they're allowed.
+/// * `build_events` - Sends the events of builds to their subscribers.
+///
+/// Returns:
+/// [`Result<(), DocsServerError>`] -> Why the server couldn't run, if it couldn't.
+async fn run(
+    mut args: Args,
+    rebuilds: mpsc::UnboundedReceiver<RebuildRequest>,
+    remote_rebuilds: Option<RebuildHandle>,
+    build_events: broadcast::Sender<BuildEvent>,
+) -> Result<(), DocsServerError> {
+    // Taken before the server starts any task or process, so that none of them reads the
+    // environment while systemd's variables are unset, nor inherits the sockets.
+    let mut inherited = inherited_listeners(&args.listen_fd)?;
+
+    // Read once, since the standard input can't be read again for every variant.
+    if let Some(path) = &args.packages_from {
+        for name in listed_packages(path).await? {
+            if !args.package.contains(&name) {
+                args.package.push(name);
+            }
+        }
+    }
+
+    if let Some(theme) = &args.default_theme {
+        let themes = rustdoc_themes();
+
+        if !themes.contains(theme) {
+            let themes: Vec<_> = themes.iter().map(|v| format!("`{v}`")).collect();
+
+            return Err(DocsServerError::Usage(format!(
+                "Unknown theme `{theme}`! The available themes are {}.",
+                list(&themes)
+            )));
+        }
+    }
+
+    if let Some(url) = &args.public_url
+        && !(url.starts_with("http://") || url.starts_with("https://"))
+    {
+        return Err(DocsServerError::Usage(format!(
+            "The public URL `{url}` must start with `http://` or `https://`!"
+        )));
+    }
+
+    if args.print_doc_dir {
+        let target_directory = match &args.target_dir {
+            Some(dir) => std::path::absolute(dir)?,
+            None => cargo_target_directory(&args)?,
+        };
+
+        println!("{}", doc_root(&args, &target_directory).display());
+        return Ok(());
+    }
+
+    let open_path = args.open_path.as_deref().map(open_path).transpose()?;
+
+    // With `--fail-fast`, the server is only bound once the initial build succeeded.
+    let listeners = (!args.fail_fast)
+        .then(|| listen(&args, std::mem::take(&mut inherited)))
+        .transpose()?;
+
+    // Known once the server is bound, which with `--fail-fast` is only once the initial build
+    // succeeded.
+    let open_url = Arc::new(OnceLock::new());
+
+    // Kept until the server stops, and removed then.
+    let (docs, mut starts, _temp_targets) =
+        if args.variant.is_empty() && args.feature_set.is_empty() {
+            // Like the page the server's opened at, with `--open-path`.
+            let reopen_url = args
+                .open_on_rebuild
+                .then(|| (open_url.clone(), open_path.unwrap_or_default().to_string()));
+            let site = prepare(
+                args.clone(),
+                rebuilds,
+                remote_rebuilds,
+                build_events,
+                reopen_url,
+                None,
+            )
+            .await?;
+
+            (
+                site.docs,
+                vec![site.start],
+                Vec::from_iter(site.temp_target),
+            )
+        } else {
+            variants(&args, rebuilds, remote_rebuilds, build_events, &open_url).await?
+        };
+
+    // The initial builds, and then what watches the sources, which are all stopped along with the
+    // servers before the temporary target directories are removed, however the server stops.
+    let mut starting = JoinSet::new();
+    let mut watches = JoinSet::new();
+    let (stop, stopped) = watch::channel(false);
+    let mut servers = JoinSet::new();
+
+    // Listened for from the start, so that stopping the server during a `--fail-fast` initial build
+    // still goes through the cleanup below.
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
+    let served: Result<(), DocsServerError> = async {
+        // Nothing's served, nor the browser opened, unless the initial build succeeded.
+        if args.fail_fast {
+            let initial = async {
+                for start in std::mem::take(&mut starts) {
+                    for watch in start.await? {
+                        watches.spawn(watch);
+                    }
+                }
+
+                Ok::<_, DocsServerError>(())
+            };
+
+            tokio::select! {
+                result = initial => result?,
+                signal = &mut shutdown => {
+                    log::info!("Received {signal}, shutting down...");
+                    return Ok(());
+                }
+            }
+        }
+
+        let listeners = match listeners {
+            Some(listeners) => listeners,
+            None => listen(&args, inherited)?,
+        };
+
+        let addresses = listeners
+            .iter()
+            .map(|listener| listener.local_addr())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        #[cfg(unix)]
+        let unix_listener = args.unix_socket.as_deref().map(bind_unix).transpose()?;
+
+        #[cfg(not(unix))]
+        if args.unix_socket.is_some() {
+            return Err(DocsServerError::Usage(
+                "Serving at a Unix socket with `--unix-socket` is only supported on Unix!"
+                    .to_string(),
+            ));
+        }
+
+        let open_url = open_url.get_or_init(|| openable_address(most_local_address(&addresses)));
+        let open_page = format!("{open_url}{}", open_path.unwrap_or_default());
+
+        let address_names: Vec<_> = addresses.iter().map(|v| v.to_string()).collect();
+
+        log::info!(
+            "Starting documentation server on {} {}...",
+            if address_names.len() == 1 {
+                "address"
+            } else {
+                "addresses"
+            },
+            list(&address_names)
+        );
+
+        let docs = docs.layer(RequestBodyLimitLayer::new(args.max_request_size));
+
+        let (reconnected, mut reconnected_rx) = watch::channel(false);
+
+        let docs = axum::middleware::from_fn_with_state(
+            args.no_reopen.then_some(reconnected),
+            middleware::track_reconnects,
+        )
+        .layer(docs);
+
+        // Outside of everything else so that requests are logged as they came in.
+        let docs = axum::middleware::from_fn_with_state(
+            args.access_log.then_some(args.max_log_uri_length),
+            middleware::access_log,
+        )
+        .layer(docs);
+
+        let keepalive = (args.tcp_keepalive > 0)
+            .then(|| TcpKeepalive::new().with_time(Duration::from_secs(args.tcp_keepalive)));
+
+        for (listener, address) in listeners.into_iter().zip(&addresses) {
+            log::info!(
+                "Documentation server is running on {}",
+                openable_address(address)
+            );
+
+            let docs = docs.clone();
+            let mut stopped = stopped.clone();
+            let keepalive = keepalive.clone();
+
+            // Probing idle connections lets ones whose client went away be dropped, instead of being
+            // held onto until they time out.
+            let listener = listener.tap_io(move |stream| {
+                if let Some(keepalive) = &keepalive
+                    && let Err(e) = SockRef::from(&*stream).set_tcp_keepalive(keepalive)
+                {
+                    log::debug!("Could not enable TCP keepalive on a connection: {e}");
+                }
+            });
+
+            servers.spawn(async move {
+                axum::serve(
+                    listener,
+                    docs.into_make_service_with_connect_info::<SocketAddr>(),
+                )
+                .with_graceful_shutdown(async move {
+                    stopped.wait_for(|stopped| *stopped).await.ok();
+                })
+                .await
+            });
+        }
+
+        // Unix sockets have no address to tell clients apart by in the access log.
+        #[cfg(unix)]
+        if let (Some(listener), Some(path)) = (unix_listener, &args.unix_socket) {
+            log::info!(
+                "Documentation server is running on the Unix socket `{}`",
+                path.display()
+            );
+
+            let docs = docs.clone();
+            let mut stopped = stopped.clone();
+
+            servers.spawn(async move {
+                axum::serve(listener, docs.into_make_service())
+                    .with_graceful_shutdown(async move {
+                        stopped.wait_for(|stopped| *stopped).await.ok();
+                    })
+                    .await
+            });
+        }
+
+        // Browsers can't open Unix sockets, so they're opened at the TCP address.
+        if args.open {
+            let open_url = open_page;
+            let probed = probe_address(most_local_address(&addresses));
+            let no_reopen = args.no_reopen;
+
+            tokio::spawn(async move {
+                let reconnect = reconnected_rx.wait_for(|reconnected| *reconnected);
+
+                if no_reopen && time::timeout(REOPEN_GRACE, reconnect).await.is_ok() {
+                    log::info!(
+                        "A browser tab already shows the documentation, not opening another."
+                    );
+                } else if is_reachable(probed).await {
+                    open_browser(&open_url);
+                } else {
+                    log::error!("The server isn't accepting connections at {probed}!");
+                    log::warn!("Once it is, open the documentation manually at:");
+                    logging::highlight(&open_url);
+                }
+            });
+        }
+
+        for start in starts {
+            starting.spawn(start);
+        }
+
+        let serving = async {
+            while let Some(started) = starting.join_next().await {
+                for watch in started.expect("Initial build task failed!")? {
+                    watches.spawn(watch);
+                }
+            }
+
+            while let Some(served) = servers.join_next().await {
+                served.expect("Documentation server task failed!")?;
+            }
+
+            Ok(())
+        };
+
+        tokio::select! {
+            result = serving => result,
+            signal = &mut shutdown => {
+                log::info!("Received {signal}, shutting down...");
+                Ok(())
+            }
+        }
+    }
+    .await;
+
+    // Requests being served are finished, but no new ones accepted. Builds and the watchers are
+    // stopped, killing `cargo doc` if it's running, and rebuilds requested from then on fail.
+    stop.send_replace(true);
+    starting.shutdown().await;
+    watches.shutdown().await;
+
+    while let Some(served) = servers.join_next().await {
+        served.expect("Documentation server task failed!")?;
+    }
+
+    if let Some(path) = &args.unix_socket {
+        std::fs::remove_file(path).ok();
+    }
+
+    served
+}
+
+/// Binds the TCP listeners the server accepts connections at, or takes the ones it inherited.
+///
+/// Arguments:
+/// * `args` - The options of the server.
+/// * `inherited` - The sockets taken with [`inherited_listeners`], if any.
+///
+/// Returns:
+/// [`Result<Vec<TcpListener>, DocsServerError>`] -> The listeners, or why they couldn't be bound.
+fn listen(
+    args: &Args,
+    inherited: Vec<std::net::TcpListener>,
+) -> Result<Vec<TcpListener>, DocsServerError> {
+    let mut listeners = vec![];
+
+    if inherited.is_empty() {
+        let mut addresses: Vec<SocketAddr> = vec![];
+
+        for address in &args.bind {
+            let mut address = listen_address(args, *address);
+
+            if let Some(name) = &args.interface {
+                address = interface::bind_address(name, address)?;
+            }
+
+            if !addresses.contains(&address) {
+                addresses.push(address);
+            }
+        }
+
+        for address in addresses
+            .iter()
+            .filter(|address| address.ip().is_unspecified())
+        {
+            log::warn!(
+                "Binding to {address} makes the documentation reachable from other machines on the \
+                 network! Pass `--localhost` to only serve it to this one."
+            );
+        }
+
+        for address in &addresses {
+            let listener =
+                bind(address, args.tcp_backlog).map_err(|source| DocsServerError::Bind {
+                    address: *address,
+                    source,
+                })?;
+
+            // The port picked for port 0 is only known once bound, so it's announced for tools
+            // that started the server to connect to it.
+            if address.port() == 0 {
+                announce_port(&listener.local_addr()?);
+            }
+
+            listeners.push(listener);
+        }
+    } else {
+        for listener in inherited {
+            listener.set_nonblocking(true)?;
+            listeners.push(TcpListener::from_std(listener)?);
+        }
+    }
+
+    Ok(listeners)
+}
+
+/// The address the server listens at for one given with `--bind`, like in the units written by
+/// `docs generate-service` too.
+///
+/// Arguments:
+/// * `args` - The options of the server.
+/// * `address` - The address given with `--bind`.
+///
+/// Returns:
+/// [`SocketAddr`] -> The address, on the loopback address instead with `--localhost`.
+pub(crate) fn listen_address(args: &Args, mut address: SocketAddr) -> SocketAddr {
+    if args.localhost {
+        address.set_ip(match address {
+            SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+            SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+        });
+    }
+
+    address
+}
+
+/// Waits for the process to be asked to stop, by Ctrl-C or, on Unix, `SIGTERM`.
+///
+/// Returns:
+/// [`&str`] -> The name of the signal that was received.
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => tokio::select! {
+                Ok(()) = tokio::signal::ctrl_c() => "Ctrl-C",
+                _ = terminate.recv() => "SIGTERM",
+            },
+            Err(e) => {
+                log::warn!("Could not listen for `SIGTERM`: {e}");
+                tokio::signal::ctrl_c().await.ok();
+                "Ctrl-C"
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await.ok();
+        "Ctrl-C"
+    }
+}
+
+/// The documentation of a crate, ready to be served.
+struct Site {
+    /// Serves the documentation.
+    docs: Router,
+    /// Runs the initial build, and then watches the sources.
+    start: Start,
+    /// The temporary target directory the documentation is built to, with `--tmp-target`.
+    temp_target: Option<TempTarget>,
+    /// The outcome of the documentation's builds.
+    state: Arc<RwLock<BuildState>>,
+}
+
+/// Runs the initial build of a [`Site`], returning what watches its sources from then on.
+type Start = Pin<Box<dyn Future<Output = Result<Vec<Watch>, DocsServerError>> + Send>>;
+
+/// Watches the sources of a [`Site`] and rebuilds it, until it's dropped.
+type Watch = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Prepares the documentation of every variant given with `--variant` or `--feature-set` to be
+/// served under its name.
+///
+/// Arguments:
+/// * `args` - The options of the server.
+/// * `rebuilds` - Receives requests to rebuild the documentation of every variant.
+/// * `remote_rebuilds` - The handle to rebuild every variant through on requests to
+///   [`middleware::REBUILD_PATH`], if they're allowed.
+/// * `build_events` - Sends the events of every variant's builds to their subscribers.
+/// * `open_url` - The URL the server is opened at, once it's bound.
+///
+/// Returns:
+/// [`Result<(Router, Vec<Start>, Vec<TempTarget>), DocsServerError>`] -> The documentation of the
+/// variants, what starts each of them, and their temporary target directories, or why it can't be
+/// served.
+async fn variants(
+    args: &Args,
+    rebuilds: mpsc::UnboundedReceiver<RebuildRequest>,
+    remote_rebuilds: Option<RebuildHandle>,
+    build_events: broadcast::Sender<BuildEvent>,
+    open_url: &Arc<OnceLock<String>>,
+) -> Result<(Router, Vec<Start>, Vec<TempTarget>), DocsServerError> {
+    let (variants, permits) = if args.feature_set.is_empty() {
+        (variant_args(args)?, None)
+    } else {
+        if args.feature_matrix_jobs == 0 {
+            return Err(DocsServerError::Usage(
+                "At least one feature set must be able to build at a time!".to_string(),
+            ));
+        }
+
+        let permits = Arc::new(Semaphore::new(args.feature_matrix_jobs));
+        (feature_set_args(args)?, Some(permits))
+    };
+
+    let mut sites: Vec<(String, Router)> = vec![];
+    let mut states = vec![];
+    let mut starts = vec![];
+    let mut temp_targets = vec![];
+    let mut senders = vec![];
+
+    for (name, mut variant_args) in variants {
+        if sites.iter().any(|(other, _)| other[1..] == name) {
+            return Err(DocsServerError::Usage(format!(
+                "There's more than one variant named `{name}`!"
+            )));
+        }
+
+        // Stripped on top of the prefix given with `--strip-prefix`, which is stripped first.
+        variant_args.strip_prefix = Some(name.clone());
+        variant_args.public_url = args
+            .public_url
+            .as_ref()
+            .map(|url| format!("{}/{name}", url.trim_end_matches('/')));
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        senders.push(sender);
+
+        let reopen_url = args
+            .open_on_rebuild
+            .then(|| (open_url.clone(), format!("{name}/")));
+        // Remote rebuilds rebuild every variant, so they're requested next to the index instead.
+        let site = prepare(
+            variant_args,
+            receiver,
+            None,
+            build_events.clone(),
+            reopen_url,
+            permits.clone(),
+        )
+        .await?;
+
+        states.push((name.clone(), site.state));
+        sites.push((format!("/{name}"), site.docs));
+        starts.push(site.start);
+        temp_targets.extend(site.temp_target);
+    }
+
+    let docs = if args.feature_set.is_empty() {
+        let names: Vec<_> = sites.iter().map(|(path, _)| &path[1..]).collect();
+        let index = pages::variant_index(&names);
+
+        Router::new().route("/", routing::get(|| async move { Html(index) }))
+    } else {
+        let starts_all = std::mem::take(&mut starts);
+        starts.push(Box::pin(report_feature_sets(starts_all, states.clone())) as Start);
+
+        Router::new().route(
+            "/",
+            routing::get(feature_matrix).with_state(Arc::<[_]>::from(states)),
+        )
+    };
+
+    // Rebuild requests are forwarded for as long as the variants are watched.
+    starts.push(Box::pin(async move {
+        Ok(vec![Box::pin(forward_rebuilds(rebuilds, senders)) as Watch])
+    }));
+
+    let docs = docs.fallback(routing::any(variant).with_state(Arc::<[_]>::from(sites)));
+
+    let docs = match remote_rebuilds {
+        Some(handle) => docs.route(
+            middleware::REBUILD_PATH,
+            routing::post(remote_rebuild)
+                .with_state((handle, args.rebuild_token.as_deref().map(Arc::from))),
+        ),
+        None => docs,
+    };
+
+    let strip_prefix = args
+        .strip_prefix
+        .as_deref()
+        .map(|prefix| prefix.trim_matches('/'))
+        .filter(|prefix| !prefix.is_empty())
+        .map(|prefix| Arc::from(format!("/{prefix}")));
+
+    let docs =
+        axum::middleware::from_fn_with_state(strip_prefix, middleware::strip_prefix).layer(docs);
+
+    Ok((Router::new().fallback_service(docs), starts, temp_targets))
+}
+
+/// Parses the variants given with `--variant`.
+///
+/// Arguments:
+/// * `args` - The options of the server.
+///
+/// Returns:
+/// [`Result<Vec<(String, Args)>, DocsServerError>`] -> The name and options of every variant, or
+/// why they're invalid.
+fn variant_args(args: &Args) -> Result<Vec<(String, Args)>, DocsServerError> {
+    let mut variants = vec![];
+
+    for variant in &args.variant {
+        let Some((name, path)) = variant.split_once('=') else {
+            return Err(DocsServerError::Usage(format!(
+                "Variants are given like `name=path`, not `{variant}`!"
+            )));
+        };
+
+        let name = name.trim_matches('/');
+
+        if name.is_empty() || name.contains('/') {
+            return Err(DocsServerError::Usage(format!(
+                "The name of the variant `{variant}` must be a single path segment!"
+            )));
+        }
+
+        log::info!("Preparing the `{name}` variant at `{path}`...");
+
+        let mut variant_args = args.clone();
+        variant_args.root = PathBuf::from(path);
+        variants.push((name.to_string(), variant_args));
+    }
+
+    Ok(variants)
+}
+
+/// Turns the sets of features given with `--feature-set` into variants of the crate, each built
+/// to its own directory in the target directory.
+///
+/// Arguments:
+/// * `args` - The options of the server.
+///
+/// Returns:
+/// [`Result<Vec<(String, Args)>, DocsServerError>`] -> The name and options of every set, or why
+/// they can't be built.
+fn feature_set_args(args: &Args) -> Result<Vec<(String, Args)>, DocsServerError> {
+    // Temporary target directories are already created for each set on their own.
+    let target_directory = match (&args.target_dir, args.tmp_target) {
+        (_, true) => None,
+        (Some(dir), false) => Some(dir.clone()),
+        (None, false) => Some(cargo_target_directory(args)?),
+    };
+
+    let mut sets = vec![];
+
+    for set in &args.feature_set {
+        let features: Vec<_> = set
+            .split([',', ' '])
+            .filter(|feature| !feature.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        // Features of dependencies are given like `dep/feature`, which can't be in a name.
+        let name = match features.is_empty() {
+            true if args.no_default_features => "none".to_string(),
+            true => "default".to_string(),
+            false => features.join("+").replace('/', "-"),
+        };
+
+        log::info!(
+            "Preparing the `{name}` feature set{}...",
+            match features.is_empty() {
+                true => String::new(),
+                false => format!(" with `{}`", features.join(",")),
+            }
+        );
+
+        let mut set_args = args.clone();
+        set_args.features = features;
+        set_args.target_dir = target_directory
+            .as_ref()
+            .map(|dir| dir.join("docs-server").join("feature-sets").join(&name));
+        sets.push((name, set_args));
+    }
+
+    Ok(sets)
+}
+
+/// Finds the directory to run cargo at for the crate, and the options to pass to every cargo
+/// command run there, so that what's resolved matches what's built.
+///
+/// Arguments:
+/// * `args` - The options of the server.
+///
+/// Returns:
+/// [`Result<(PathBuf, Vec<String>), DocsServerError>`] -> The directory and the options, or why
+/// the crate's manifest can't be used.
+fn cargo_root(args: &Args) -> Result<(PathBuf, Vec<String>), DocsServerError> {
+    let mut cargo_options = vec![];
+
+    // A manifest can be given instead of the directory it's at, like to cargo's `--manifest-path`.
+    let manifest = match &args.manifest_path {
+        Some(path) if !path.is_file() => {
+            return Err(DocsServerError::Usage(format!(
+                "The manifest `{}` doesn't exist!",
+                path.display()
+            )));
+        }
+        Some(path) => Some(path),
+        None => args.root.is_file().then_some(&args.root),
+    };
+
+    let root = match manifest {
+        Some(manifest) => {
+            let name = manifest.file_name().unwrap().to_string_lossy().into_owned();
+            cargo_options.append(&mut vec!["--manifest-path".to_string(), name]);
+
+            match manifest.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_owned(),
+                _ => PathBuf::from("."),
+            }
+        }
+        None => args.root.clone(),
+    };
+
+    if !args.features.is_empty() {
+        cargo_options.append(&mut vec!["--features".to_string(), args.features.join(",")]);
+    }
+
+    for (set, flag) in [
+        (args.all_features, "--all-features"),
+        (args.no_default_features, "--no-default-features"),
+        (args.offline, "--offline"),
+        (args.frozen, "--frozen"),
+        (args.locked, "--locked"),
+    ] {
+        if set {
+            cargo_options.push(flag.to_string());
+        }
+    }
+
+    Ok((root, cargo_options))
+}
+
+/// Finds cargo's target directory for the crate, without resolving its dependencies.
+///
+/// Arguments:
+/// * `args` - The options of the server.
+///
+/// Returns:
+/// [`Result<PathBuf, DocsServerError>`] -> The target directory, or why cargo couldn't tell.
+fn cargo_target_directory(args: &Args) -> Result<PathBuf, DocsServerError> {
+    let (root, cargo_options) = cargo_root(args)?;
+
+    let metadata = MetadataCommand::new()
+        .current_dir(root)
+        .no_deps()
+        .other_options(cargo_options)
+        .exec()?;
+
+    Ok(metadata.target_directory.into_std_path_buf())
+}
+
+/// The directory the documentation is generated at, in the target directory.
+///
+/// Arguments:
+/// * `args` - The options of the server.
+/// * `target_directory` - The target directory the documentation is built to.
+///
+/// Returns:
+/// [`PathBuf`] -> The directory, under the platform's when building for one with `--target`.
+fn doc_root(args: &Args, target_directory: &Path) -> PathBuf {
+    match &args.target {
+        Some(target) => target_directory.join(target).join("doc"),
+        None => target_directory.join("doc"),
+    }
+}
+
+/// Binds a TCP listener to an address, with as many pending connections queued as `backlog`
+/// allows.
+///
+/// Arguments:
+/// * `address` - The address to bind to.
+/// * `backlog` - How many connections can wait to be accepted.
+///
+/// Returns:
+/// [`io::Result<TcpListener>`] -> The listener, or why it couldn't be bound.
+fn bind(address: &SocketAddr, backlog: u32) -> io::Result<TcpListener> {
+    let socket = match address {
+        SocketAddr::V4(_) => TcpSocket::new_v4()?,
+        SocketAddr::V6(_) => TcpSocket::new_v6()?,
+    };
+
+    // Like `TcpListener::bind`, so that restarting the server doesn't wait for the connections of
+    // the last run to time out.
+    #[cfg(unix)]
+    socket.set_reuseaddr(true)?;
+
+    socket.bind(*address)?;
+    socket.listen(backlog)
+}
+
+/// Runs the initial builds of every feature set given with `--feature-set`, and then logs which
+/// of them built.
+///
+/// Arguments:
+/// * `starts` - What starts each of the sets.
+/// * `states` - The outcome of every set's builds, by its name.
+///
+/// Returns:
+/// [`Result<Vec<Watch>, DocsServerError>`] -> What watches the sets' sources, or why the builds
+/// couldn't run.
+async fn report_feature_sets(
+    starts: Vec<Start>,
+    states: Vec<(String, Arc<RwLock<BuildState>>)>,
+) -> Result<Vec<Watch>, DocsServerError> {
+    let mut starts: JoinSet<_> = starts.into_iter().collect();
+    let mut watches = vec![];
+
+    while let Some(started) = starts.join_next().await {
+        watches.extend(started.expect("Initial build task failed!")?);
+    }
+
+    let (built, failed): (Vec<_>, Vec<_>) = states
+        .iter()
+        .partition(|(_, state)| state.read().unwrap().failure.is_none());
+
+    let names = |sets: Vec<&(String, _)>| -> Vec<_> {
+        sets.into_iter()
+            .map(|(name, _)| format!("`{name}`"))
+            .collect()
+    };
+
+    if failed.is_empty() {
+        log::info!("Every feature set built.");
+    } else if built.is_empty() {
+        log::warn!("No feature set built!");
+    } else {
+        log::warn!(
+            "The {} feature sets built, but {} didn't!",
+            list(&names(built)),
+            list(&names(failed))
+        );
+    }
+
+    Ok(watches)
+}
+
+/// The outcome of the builds of every feature set given with `--feature-set`, by its name.
+type FeatureSets = Arc<[(String, Arc<RwLock<BuildState>>)]>;
+
+/// Serves an index of every feature set given with `--feature-set`, with whether each of them
+/// built.
+///
+/// Arguments:
+/// * `sets` - The outcome of every set's builds, by its name.
+///
+/// Returns:
+/// [`Html<String>`] -> The index.
+async fn feature_matrix(State(sets): State<FeatureSets>) -> Html<String> {
+    let sets: Vec<_> = sets
+        .iter()
+        .map(|(name, state)| (name.as_str(), state.read().unwrap().clone()))
+        .collect();
+
+    Html(pages::feature_matrix(&sets))
+}
+
+/// Serves a request with the documentation of the variant its path is under.
+///
+/// Arguments:
+/// * `sites` - The documentation of every variant, by the path it's served under.
+/// * `request` - The incoming request.
+///
+/// Returns:
+/// [`Response`] -> The variant's response, or a 404 response if it isn't under any.
+async fn variant(State(sites): State<Arc<[(String, Router)]>>, request: Request) -> Response {
+    let path = request.uri().path();
+
+    let site = sites.iter().find(|(prefix, _)| {
+        path.strip_prefix(prefix.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    });
+
+    match site {
+        Some((_, docs)) => docs
+            .clone()
+            .oneshot(request)
+            .await
+            .unwrap_or_else(|e| match e {}),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Rebuilds the documentation on a request to [`middleware::REBUILD_PATH`], answering once the
+/// rebuild finishes.
+///
+/// The request's body is never read, whatever it is.
+///
+/// Arguments:
+/// * `handle` - The handle to request the rebuild through.
+/// * `token` - The secret the request must carry in its `Authorization` header, if any.
+/// * `headers` - The request's headers.
+///
+/// Returns:
+/// [`Response`] -> `200 OK` if the rebuild succeeded, `500 Internal Server Error` with the output
+/// of the build if it failed, `401 Unauthorized` if the request didn't carry the token, or
+/// `503 Service Unavailable` if the server is stopping.
+async fn remote_rebuild(
+    State((handle, token)): State<(RebuildHandle, Option<Arc<str>>)>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(token) = token {
+        let given = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        if !given.is_some_and(|given| same_secret(given, &token)) {
+            return (
+                StatusCode::UNAUTHORIZED,
+                [(WWW_AUTHENTICATE, "Bearer")],
+                "A valid rebuild token is required.",
+            )
+                .into_response();
+        }
+    }
+
+    match handle.rebuild().await {
+        Ok(()) => "OK".into_response(),
+        Err(RebuildError::Failed(failure)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("{failure}\n\n{}", failure.output),
+        )
+            .into_response(),
+        Err(e @ RebuildError::Stopped) => {
+            (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Compares two secrets in a time that doesn't depend on how much of them matches, so that they
+/// can't be guessed by timing the comparisons.
+///
+/// Arguments:
+/// * `given` - The secret a request carried.
+/// * `expected` - The secret it must be.
+///
+/// Returns:
+/// [`bool`] -> Whether they're the same.
+fn same_secret(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Where the documentation is served from.
+#[derive(Clone)]
+enum Served {
+    /// The directory it's generated at.
+    Dir(PathBuf),
+    /// The latest copy of it, with `--atomic-swap`.
+    Snapshots(Snapshots),
+}
+
+/// Serves a sitemap of every page of the documentation, listing what's there at the time of the
+/// request so that it's current after every build.
+///
+/// Arguments:
+/// * `base` - The URL the documentation is reachable at from outside, without a trailing slash.
+/// * `served` - Where the documentation is served from.
+///
+/// Returns:
+/// [`Response`] -> The sitemap.
+async fn sitemap(State((base, served)): State<(Arc<str>, Served)>) -> Response {
+    let dir = match served {
+        Served::Dir(dir) => dir,
+        Served::Snapshots(snapshots) => snapshots.current(),
+    };
+
+    let paths = tokio::task::spawn_blocking(move || html_files(&dir))
+        .await
+        .unwrap_or_default();
+
+    (
+        [(CONTENT_TYPE, "application/xml")],
+        pages::sitemap(&base, &paths),
+    )
+        .into_response()
+}
+
+/// Forwards requests to rebuild the documentation to every variant, answering them once all of
+/// the rebuilds finish.
+///
+/// Arguments:
+/// * `rebuilds` - Receives requests to rebuild the documentation.
+/// * `variants` - Send rebuild requests to each variant.
+async fn forward_rebuilds(
+    mut rebuilds: mpsc::UnboundedReceiver<RebuildRequest>,
+    variants: Vec<mpsc::UnboundedSender<RebuildRequest>>,
+) {
+    while let Some(request) = rebuilds.recv().await {
+        let mut outcomes = vec![];
+
+        for variant in &variants {
+            let (sender, receiver) = oneshot::channel();
+
+            if variant.send(sender).is_ok() {
+                outcomes.push(receiver);
+            }
+        }
+
+        let mut result = Ok(());
+
+        for outcome in outcomes {
+            if let Ok(Err(failure)) = outcome.await
+                && result.is_ok()
+            {
+                result = Err(failure);
+            }
+        }
+
+        request.send(result).ok();
+    }
+}
+
+/// Prepares the documentation of a crate to be served, without building it yet.
+///
+/// Arguments:
+/// * `args` - The options of the server.
+/// * `rebuilds` - Receives requests to rebuild the documentation.
+/// * `remote_rebuilds` - The handle to rebuild the documentation through on requests to
+///   [`middleware::REBUILD_PATH`], if they're allowed.
+/// * `build_events` - Sends the events of builds to their subscribers.
+/// * `reopen_url` - Where to open the browser at again after every successful rebuild, if
+///   anywhere: the URL the server is opened at, once it's bound, and the path under it.
+/// * `permits` - Limits how many builds run at the same time as the rest sharing them, if they're
+///   limited.
+///
+/// Returns:
+/// [`Result<Site, DocsServerError>`] -> The documentation, or why it can't be served.
+async fn prepare(
+    args: Args,
+    mut rebuilds: mpsc::UnboundedReceiver<RebuildRequest>,
+    remote_rebuilds: Option<RebuildHandle>,
+    build_events: broadcast::Sender<BuildEvent>,
+    reopen_url: Option<(Arc<OnceLock<String>>, String)>,
+    permits: Option<Arc<Semaphore>>,
+) -> Result<Site, DocsServerError> {
+    let redirects = redirect_map(&args.redirects)?;
+    let (root, mut cargo_options) = cargo_root(&args)?;
+
+    if args.require_clean {
+        require_clean(&root).await?;
+    }
+
+    let root_canonical = fs::canonicalize(&root).await?;
+
+    log::info!("Getting cargo metadata...");
+
+    let metadata = MetadataCommand::new()
+        .current_dir(&root)
+        .other_options(cargo_options.clone())
+        .exec()?;
+
+    let mut packages = vec![];
+
+    if args.workspace {
+        for package in metadata.workspace_packages() {
+            if !args.exclude.contains(&package.name) {
+                packages.push(package.clone());
+            }
+        }
+    } else if args.package.is_empty() && args.package_regex.is_none() {
+        // Like cargo, prefer the innermost package the root is in over the manifest's root one.
+        let containing = metadata
+            .workspace_packages()
+            .into_iter()
+            .filter(|package| root_canonical.starts_with(package.manifest_path.parent().unwrap()))
+            .max_by_key(|package| package.manifest_path.components().count());
+
+        if let Some(package) = containing.or(metadata.root_package()) {
+            packages.push(package.clone());
+        } else {
+            // A virtual manifest has no root package, so fall back to the workspace's default
+            // members like `cargo doc` itself does.
+            if metadata.workspace_default_members.is_available() {
+                for package in metadata.workspace_default_packages() {
+                    packages.push(package.clone());
+                }
+            }
+
+            if packages.is_empty() {
+                let members: Vec<_> = metadata
+                    .workspace_packages()
+                    .iter()
+                    .map(|v| format!("`{}`", v.name))
+                    .collect();
+
+                return Err(DocsServerError::Usage(format!(
+                    "{} is a virtual manifest! Choose the packages to document with `--package` or \
+                     `--workspace`. The workspace members are {}.",
+                    metadata.workspace_root.join("Cargo.toml"),
+                    list(&members)
+                )));
+            }
+
+            log::info!(
+                "{} is a virtual manifest, documenting the workspace's default members...",
+                metadata.workspace_root.join("Cargo.toml")
+            );
+        }
+    } else {
+        // Anchored so that the whole name has to match, like `--package` does.
+        let regex = args
+            .package_regex
+            .as_ref()
+            .map(|pattern| {
+                // Checked on its own first, so that errors point at what was given.
+                Regex::new(pattern)
+                    .and_then(|_| Regex::new(&format!("^(?:{pattern})$")))
+                    .map_err(|e| {
+                        DocsServerError::Usage(format!(
+                            "`{pattern}` isn't a valid regular expression: {e}"
+                        ))
+                    })
+            })
+            .transpose()?;
+
+        let matches = |package: &Package| regex.as_ref().is_some_and(|r| r.is_match(&package.name));
+
+        for package in metadata.workspace_packages() {
+            if args.package.contains(&package.name) || matches(package) {
+                packages.push(package.clone());
+            }
+        }
+
+        let mut unknown = vec![];
+
+        for name in &args.package {
+            if packages.iter().any(|package| *package.name == *name) {
+                continue;
+            }
+
+            let candidates: Vec<_> = metadata
+                .packages
+                .iter()
+                .filter(|package| *package.name == *name)
+                .collect();
+
+            // Dependencies checked out locally can be browsed on their own, like while reading
+            // their sources, but those in cargo's caches are read-only, so only `--doc-dep`
+            // documents them.
+            match candidates.iter().find(|package| package.source.is_none()) {
+                Some(package) => {
+                    log::info!(
+                        "`{name}` isn't a member of the workspace, documenting the dependency \
+                         checked out at `{}` instead...",
+                        package.manifest_path.parent().unwrap()
+                    );
+
+                    packages.push((*package).clone());
+                }
+                None if candidates.is_empty() => unknown.push(format!("`{name}`")),
+                None => {
+                    return Err(DocsServerError::Usage(format!(
+                        "`{name}` is a dependency from {}, whose sources cargo keeps read-only \
+                         in its caches! Pass `--doc-dep {name}` to document it alongside the \
+                         crate instead, or check it out locally and patch it in with `[patch]`.",
+                        source_name(candidates[0].source.as_ref().unwrap())
+                    )));
+                }
+            }
+        }
+
+        // Names that aren't packages at all are likely typos, which would otherwise leave the
+        // package out without a word.
+        if !unknown.is_empty() {
+            let members: Vec<_> = metadata
+                .workspace_packages()
+                .iter()
+                .map(|v| format!("`{}`", v.name))
+                .collect();
+
+            return Err(DocsServerError::Usage(format!(
+                "Unknown {} {}! The workspace members are {}.",
+                if unknown.len() == 1 {
+                    "package"
+                } else {
+                    "packages"
+                },
+                list(&unknown),
+                list(&members)
+            )));
+        }
+
+        if let Some(pattern) = &args.package_regex
+            && !packages.iter().any(matches)
+        {
+            let members: Vec<_> = metadata
+                .workspace_packages()
+                .iter()
+                .map(|v| format!("`{}`", v.name))
+                .collect();
+
+            return Err(DocsServerError::Usage(format!(
+                "No package in the workspace matches `{pattern}`! The workspace members are {}.",
+                list(&members)
+            )));
+        }
+    }
+
+    if packages.is_empty() {
+        return Err(DocsServerError::Usage(
+            concat!(
+                "No packages of the ones specified were found! Make sure you've specified ",
+                "`--package`, `--workspace`, and `--exclude` properly."
+            )
+            .to_string(),
+        ));
+    }
+
+    let target = match &args.example {
+        Some(example) => packages
+            .iter()
+            .flat_map(|package| &package.targets)
+            .find(|target| target.is_example() && target.name == *example)
+            .ok_or_else(|| {
+                DocsServerError::Usage(format!(
+                    "No example named `{example}` was found in the selected packages!"
+                ))
+            })?,
+        None => find_ideal_target(&packages).ok_or_else(|| {
+            DocsServerError::Usage("There was no target to make documentation for!".to_string())
+        })?,
+    };
+    let target = target.clone();
+
+    for dep in &args.doc_deps {
+        if !metadata
+            .packages
+            .iter()
+            .any(|package| *package.name == *dep)
+        {
+            return Err(DocsServerError::Usage(format!(
+                "The dependency `{dep}` was not found in the dependency graph!"
+            )));
+        }
+    }
+
+    let package_names: Vec<_> = packages
+        .iter()
+        .map(|v| v.name.to_string())
+        .chain(args.doc_deps.iter().cloned())
+        .map(|v| format!("`{v}`"))
+        .collect();
+
+    log::info!("Compiling documentation for {}...", list(&package_names));
+
+    let mut cargo_args = vec!["doc".to_string(), "--no-deps".to_string()];
+
+    for package in &packages {
+        cargo_args.append(&mut vec!["--package".to_string(), package.name.to_string()]);
+    }
+
+    // Dependencies whose private items are displayed are documented on their own, since
+    // `--document-private-items` applies to every package `cargo doc` documents.
+    if !args.doc_private_deps {
+        for dep in &args.doc_deps {
+            cargo_args.append(&mut vec!["--package".to_string(), dep.clone()]);
+        }
+    }
+
+    if !args.skip_feature.is_empty() {
+        for feature in &args.skip_feature {
+            if !packages.iter().any(|p| p.features.contains_key(feature)) {
+                return Err(DocsServerError::Usage(format!(
+                    "None of the selected packages has a feature named `{feature}`!"
+                )));
+            }
+        }
+
+        // Metadata is still resolved with every feature, which is a superset of the ones built.
+        let features: Vec<_> = packages
+            .iter()
+            .flat_map(|package| {
+                features_without(package, &args.skip_feature)
+                    .into_iter()
+                    .map(|feature| format!("{}/{feature}", package.name))
+            })
+            .collect();
+
+        cargo_options.retain(|option| option != "--all-features");
+        cargo_options.push("--no-default-features".to_string());
+
+        if !features.is_empty() {
+            cargo_options.append(&mut vec!["--features".to_string(), features.join(",")]);
+        }
+    }
+
+    // Options for the whole build, rather than for the selected packages, like features are, which
+    // the dependencies documented on their own are built with too.
+    let mut shared_args = vec![];
+    let mut cargo_options = cargo_options.into_iter();
+
+    while let Some(option) = cargo_options.next() {
+        match option.as_str() {
+            "--manifest-path" => {
+                shared_args.extend([option].into_iter().chain(cargo_options.next()))
+            }
+            "--offline" | "--frozen" | "--locked" => shared_args.push(option),
+            _ => cargo_args.push(option),
+        }
+    }
+
+    if let Some(jobs) = args.jobs {
+        shared_args.append(&mut vec!["--jobs".to_string(), jobs.to_string()]);
+    }
+
+    if args.with_private {
+        cargo_args.push("--document-private-items".to_string());
+    }
+
+    // `RUSTDOCFLAGS` replaces the flags configured for cargo, so it's added to when it's set
+    // instead of configuring more.
+    let rustdoc_flags = match std::env::var("RUSTDOCFLAGS") {
+        Ok(flags) if args.deny_warnings => Some(format!("{flags} -D warnings")),
+        _ => {
+            if args.deny_warnings {
+                shared_args.append(&mut vec![
+                    "--config".to_string(),
+                    r#"build.rustdocflags=["-D", "warnings"]"#.to_string(),
+                ]);
+            }
+
+            None
+        }
+    };
+
+    if let Some(target) = &args.target {
+        shared_args.append(&mut vec!["--target".to_string(), target.clone()]);
+    }
+
+    // Which of the packages' targets are documented, left out of rebuilds that only document the
+    // targets whose sources changed.
+    let mut selection = vec![];
+
+    if args.all_targets {
+        if packages
+            .iter()
+            .any(|p| p.targets.iter().any(|t| t.is_lib()))
+        {
+            selection.push("--lib".to_string());
+        }
+
+        selection.push("--bins".to_string());
+        selection.push("--examples".to_string());
+    }
+
+    if let Some(example) = &args.example {
+        selection.append(&mut vec!["--example".to_string(), example.clone()]);
+    }
+
+    // Diagnostics are parsed to point their locations at absolute paths, and kept colored for
+    // the build error page.
+    shared_args.push("--message-format=json-diagnostic-rendered-ansi".to_string());
+
+    let no_std: Vec<_> = packages
+        .iter()
+        .filter(|p| p.targets.iter().any(|t| t.is_lib() && is_no_std(t)))
+        .map(|p| format!("`{}`", p.name))
+        .collect();
+
+    // Crates without `std` often don't build for the host, and cargo's errors don't say why.
+    let hint = (args.target.is_none() && !no_std.is_empty()).then(|| {
+        format!(
+            "{} {} `#![no_std]` and may not build for the host. Try passing a supported target \
+             with `--target <TRIPLE>`.",
+            list(&no_std),
+            if no_std.len() == 1 { "is" } else { "are" },
+        )
+    });
+
+    let state = Arc::new(RwLock::new(BuildState::default()));
+    let reloader = Reloader::new(Duration::from_millis(args.reload_debounce));
+    let (building, building_rx) = watch::channel(false);
+
+    let temp_target = args.tmp_target.then(TempTarget::create).transpose()?;
+
+    let target_directory = match (&temp_target, &args.target_dir) {
+        (Some(temp_target), _) => Some(temp_target.path().to_owned()),
+        (None, Some(dir)) => Some(std::path::absolute(dir)?),
+        (None, None) => None,
+    };
+
+    let target_directory = match target_directory {
+        Some(dir) => {
+            log::info!("Building to `{}`...", dir.display());
+
+            shared_args.append(&mut vec![
+                "--target-dir".to_string(),
+                dir.display().to_string(),
+            ]);
+
+            Utf8PathBuf::from_path_buf(dir).map_err(|dir| {
+                DocsServerError::Usage(format!(
+                    "The target directory `{}` isn't valid UTF-8!",
+                    dir.display()
+                ))
+            })?
+        }
+        None => metadata.target_directory.clone(),
+    };
+
+    let doc_root = Utf8PathBuf::from_path_buf(doc_root(&args, target_directory.as_std_path()))
+        .expect("The target directory is valid UTF-8, and so is the target.");
+
+    let snapshots = if args.atomic_swap {
+        let dir = target_directory.join("docs-server").join("snapshots");
+        Some(
+            Snapshots::new(
+                doc_root.clone().into_std_path_buf(),
+                dir.into_std_path_buf(),
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    let scope = match packages.as_slice() {
+        [package] if args.doc_deps.is_empty() && args.example.is_none() => {
+            target_scope(package, args.all_targets, &root_canonical)
+        }
+        _ => None,
+    };
+
+    cargo_args.extend(shared_args.iter().cloned());
+
+    // `cargo rustdoc` passes what follows `--` to rustdoc for that package only.
+    let private_deps = if args.doc_private_deps {
+        args.doc_deps
+            .iter()
+            .map(|dep| {
+                let rustdoc = ["rustdoc", "--package", dep, "--lib"].map(String::from);
+                let private = ["--", "--document-private-items"].map(String::from);
+
+                [rustdoc.as_slice(), &shared_args, &private].concat()
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+
+    // With `--workspace`, the members that built are told apart from those that didn't by cargo's
+    // messages, and cargo keeps going past the ones that fail so that the rest are still served.
+    let members: Vec<_> = if args.workspace && packages.len() > 1 {
+        packages
+            .iter()
+            .map(|package| (package.name.to_string(), package.id.repr.clone()))
+            .collect()
+    } else {
+        vec![]
+    };
+
+    if !members.is_empty() {
+        cargo_args.push("--keep-going".to_string());
+    }
+
+    let builder = Builder {
+        root: root.clone(),
+        private_deps,
+        cargo_args: [cargo_args.clone(), selection].concat(),
+        members,
+        scope: scope.map(|scope| (cargo_args, scope)),
+        rustdoc_flags,
+        timeout: args.build_timeout.map(Duration::from_secs),
+        workspace_root: metadata.workspace_root.clone().into_std_path_buf(),
+        pre_build: args.pre_build.clone(),
+        // Variants build at the same time, and their spinners would overwrite each other.
+        spinner: !args.quiet
+            && std::io::stderr().is_terminal()
+            && args.variant.is_empty()
+            && args.feature_set.is_empty(),
+        hint,
+        terminal_status: (args.terminal_status && std::io::stdout().is_terminal()).then(|| {
+            packages
+                .iter()
+                .map(|v| v.name.to_string())
+                .chain(args.doc_deps.iter().cloned())
+                .collect::<Vec<_>>()
+                .join(", ")
+        }),
+        terminal_bell: args.terminal_bell,
+        state: state.clone(),
+        reloader: reloader.clone(),
+        building,
+        build_events,
+        build_log: args.build_log.clone(),
+        doc_root: doc_root.clone().into_std_path_buf(),
+        on_success: args.on_success.clone(),
+        on_failure: args.on_failure.clone(),
+        snapshots: snapshots.clone(),
+        // Without a name, `/` never redirects to the target, so there's no index to check for.
+        served_index: (!doc_dir(&target).is_empty())
+            .then(|| (target_directory.clone().into_std_path_buf(), target.clone())),
+        follow_edits: args.follow_edits.then(|| {
+            let mut targets: Vec<_> = packages
+                .iter()
+                .flat_map(|package| package.targets.iter())
+                .filter(|t| is_documented(t, args.all_targets))
+                .collect();
+
+            // Libraries come first, since binaries next to them share their sources' directory.
+            targets.sort_by_key(|t| !t.is_lib());
+
+            let targets = targets
+                .into_iter()
+                .map(|t| (t.src_path.clone().into_std_path_buf(), doc_dir(t)))
+                .collect();
+
+            EditFollower::new(
+                root_canonical.clone(),
+                doc_root.clone().into_std_path_buf(),
+                targets,
+            )
+        }),
+        pages: args.smart_reload.then(Default::default),
+        permits,
+    };
+
+    if args.clean {
+        log::info!("Removing previously generated documentation...");
+
+        let dep_targets = metadata
+            .packages
+            .iter()
+            .filter(|package| args.doc_deps.iter().any(|dep| *package.name == *dep))
+            .flat_map(|package| package.targets.iter().filter(|t| t.is_lib()));
+
+        let targets = packages
+            .iter()
+            .flat_map(|package| package.targets.iter().filter(|t| is_documented(t, true)))
+            .chain(dep_targets);
+
+        for target in targets {
+            // Sources are rendered to `src/<crate>/`, next to the crates' directories.
+            for dir in [
+                doc_root.join(doc_dir(target)),
+                doc_root.join("src").join(doc_dir(target)),
+            ] {
+                match fs::remove_dir_all(&dir).await {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => log::warn!("Could not remove `{dir}`: {e}"),
+                }
+            }
+        }
+    }
+
+    if doc_dir(&target).is_empty() {
+        log::warn!(
+            "The documented target has an empty name! Serving an index of the targets at `/` \
+             instead of redirecting to it."
+        );
+    }
+
+    let guide = match &args.markdown_dir {
+        Some(dir) => {
+            let dir = fs::canonicalize(dir).await.map_err(|e| {
+                DocsServerError::Usage(format!(
+                    "Could not read the Markdown directory `{}`: {e}",
+                    dir.display()
+                ))
+            })?;
+
+            log::info!("Rendering the guide at `{}`...", dir.display());
+
+            Some(Guide::new(dir))
+        }
+        None => None,
+    };
+
+    let custom_css = match &args.custom_css {
+        Some(path) => Some(fs::canonicalize(path).await.map_err(|e| {
+            DocsServerError::Usage(format!(
+                "Could not read the custom stylesheet `{}`: {e}",
+                path.display()
+            ))
+        })?),
+        None => None,
+    };
+
+    let mut watch_paths = BTreeSet::new();
+
+    for package in &packages {
+        let src = package.manifest_path.parent().unwrap().join("src");
+
+        if args.all_targets {
+            // Examples usually live outside of `src/`, so their directories are watched too.
+            for target in package.targets.iter().filter(|t| is_documented(t, true)) {
+                if let Some(dir) = target.src_path.parent()
+                    && !dir.starts_with(&src)
+                {
+                    watch_paths.insert(dir.as_std_path().to_owned());
+                }
+            }
+        }
+
+        watch_paths.insert(src.into_std_path_buf());
+    }
+
+    if args.example.is_some() {
+        // Examples made of many files have them next to their `main.rs`.
+        let path = if target.src_path.file_name() == Some("main.rs") {
+            target.src_path.parent().unwrap()
+        } else {
+            &target.src_path
+        };
+
+        watch_paths.insert(path.as_std_path().to_owned());
+    }
+
+    if args.rebuild_on_dependency_source_change {
+        for package in path_dependencies(&metadata, &packages) {
+            log::info!("Watching the sources of `{}`...", package.name);
+
+            let src = package.manifest_path.parent().unwrap().join("src");
+            watch_paths.insert(src.into_std_path_buf());
+        }
+    }
+
+    // Dependencies documented with `--doc-dep` that are checked out locally, like forks patched in
+    // with `[patch]`, are edited alongside the packages. Those from registries and git are
+    // extracted to cargo's caches, which aren't meant to be edited, so they're only documented.
+    if !args.no_watch {
+        for dep in &args.doc_deps {
+            for package in metadata.packages.iter().filter(|p| *p.name == *dep) {
+                match &package.source {
+                    None => {
+                        log::info!("Watching the sources of `{dep}`...");
+
+                        let src = package.manifest_path.parent().unwrap().join("src");
+                        watch_paths.insert(src.into_std_path_buf());
+                    }
+                    Some(source) => log::info!(
+                        "`{dep}` comes from {}, so its sources aren't watched.",
+                        source_name(source)
+                    ),
+                }
+            }
+        }
+    }
+
+    if !args.watch_only.is_empty() {
+        watch_paths = watched_subset(&args.watch_only, &root_canonical, &watch_paths).await?;
+    }
+
+    // Watches don't follow symlinks on every platform, and following them where they do would
+    // report edits twice once their targets are watched too.
+    let sources = watch_paths.clone();
+    let linked = tokio::task::spawn_blocking(move || symlink_targets(&sources))
+        .await
+        .unwrap_or_default();
+
+    for target in linked {
+        log::info!(
+            "Watching `{}`, which the sources link to...",
+            target.display()
+        );
+        watch_paths.insert(target);
+    }
+
+    if let Some(guide) = &guide {
+        watch_paths.insert(guide.dir().to_owned());
+    }
+
+    let ignored = ignore_files(&args.ignore_file);
+
+    // Builds write to the target directory, which would rebuild them again and again if it's in a
+    // watched path, like with `--target-dir src/docs`. It's created first, to be canonicalized.
+    fs::create_dir_all(&target_directory).await?;
+    let target_canonical = fs::canonicalize(&target_directory).await?;
+
+    // The sources' code is remembered, to tell edits to doc comments apart. It's read while the
+    // initial build runs, rather than holding up the server starting.
+    let scanning = {
+        let (root, paths) = (root_canonical.clone(), watch_paths.clone());
+        tokio::task::spawn_blocking(move || CommentChanges::new(root, &paths))
+    };
+
+    // Changes to the guide only need it to be rendered again, not the documentation rebuilt.
+    let guide_path = guide
+        .as_ref()
+        .map(|guide| pathdiff::diff_paths(guide.dir(), &root_canonical).unwrap_or_default());
+
+    // Nor do changes to the custom stylesheet, which pages load apart from the documentation.
+    let css_path = custom_css
+        .as_ref()
+        .map(|css| pathdiff::diff_paths(css, &root_canonical).unwrap_or_else(|| css.clone()));
+
+    let debounce_structural =
+        Duration::from_millis(args.debounce_structural.unwrap_or(args.debounce));
+    let debounce_modify = Duration::from_millis(args.debounce_modify.unwrap_or(args.debounce));
+    let min_rebuild_interval = Duration::from_millis(args.min_rebuild_interval);
+    let backoff = args
+        .rebuild_backoff
+        .map(|failures| (failures, Duration::from_millis(args.max_rebuild_backoff)));
+    let startup_grace = Duration::from_millis(args.startup_grace);
+    let warmup_quiet = args.warmup_quiet.map(Duration::from_millis);
+
+    // Events are sent with when they were received, since they can wait for a build to finish.
+    let (tx, mut rx) = mpsc::unbounded_channel::<(notify::Result<Event>, time::Instant)>();
+
+    let mut watchers: Vec<Box<dyn Watcher + Send>> = vec![];
+    let mut poll = !args.no_watch && args.watch_mode != WatchMode::Events;
+
+    if args.no_watch {
+        log::info!(
+            "Not watching the sources, the documentation is only built {}.",
+            if args.allow_remote_rebuild {
+                "on startup and when a rebuild is requested"
+            } else {
+                "on startup"
+            }
+        );
+    } else if args.watch_mode != WatchMode::Poll {
+        let tx = tx.clone();
+
+        let mut watcher = RecommendedWatcher::new(
+            move |res| {
+                tx.send((res, time::Instant::now())).ok();
+            },
+            notify::Config::default().with_follow_symlinks(false),
+        )?;
+
+        // Every watched directory takes one of the operating system's watches, which huge
+        // workspaces can run out of. Polling has no such limit, so it's better than not starting.
+        match watch_all(&mut watcher, &watch_paths) {
+            Ok(()) => watchers.push(Box::new(watcher)),
+            Err(e) if matches!(e.kind, notify::ErrorKind::MaxFilesWatch) => {
+                log::warn!(
+                    "Ran out of file watches while watching the sources, {}.",
+                    if poll {
+                        "only scanning them for changes instead".to_string()
+                    } else {
+                        format!(
+                            "scanning them for changes every {}ms instead",
+                            args.poll_interval
+                        )
+                    }
+                );
+
+                if cfg!(target_os = "linux") {
+                    log::warn!(
+                        "Raise the limit to be told about changes, like with `sudo sysctl \
+                         fs.inotify.max_user_watches=524288`, adding it to `/etc/sysctl.conf` to \
+                         keep it after rebooting."
+                    );
+                }
+
+                poll = true;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    if poll {
+        let tx = tx.clone();
+        let config = notify::Config::default()
+            .with_poll_interval(Duration::from_millis(args.poll_interval))
+            .with_follow_symlinks(false);
+
+        let mut watcher = PollWatcher::new(
+            move |res| {
+                tx.send((res, time::Instant::now())).ok();
+            },
+            config,
+        )?;
+
+        watch_all(&mut watcher, &watch_paths)?;
+        watchers.push(Box::new(watcher));
+    }
+
+    let debug_config = args.debug_endpoints.then(|| {
+        // Quoted like for a POSIX shell, so that it can be copied to run the build by hand.
+        let command = ["cargo".to_string()]
+            .iter()
+            .chain(&builder.cargo_args)
+            .map(|arg| service::shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let watching = if watchers.is_empty() {
+            "none"
+        } else if poll && watchers.len() > 1 {
+            "hybrid"
+        } else if poll {
+            "poll"
+        } else {
+            "events"
+        };
+
+        serde_json::json!({
+            "root": root_canonical,
+            "packages": packages.iter().map(|p| p.name.to_string()).collect::<Vec<_>>(),
+            "doc_deps": args.doc_deps,
+            "target": args.target,
+            "target_dir": target_directory,
+            "doc_dir": doc_root,
+            "watching": watching,
+            "watched": watch_paths,
+            "command": command,
+            "rustdocflags": builder.rustdoc_flags,
+        })
+    });
+
+    // Editors often save files by replacing them, which a watch on the stylesheet itself would
+    // lose track of, so its directory is watched instead, for changes to the stylesheet alone.
+    if let Some(css) = custom_css.as_ref().filter(|_| !args.no_watch) {
+        let dir = css.parent().unwrap_or(css).to_owned();
+        let css = css.clone();
+
+        let handler = move |res: notify::Result<Event>| {
+            let res = res.map(|mut event| {
+                event.paths.retain(|path| *path == css);
+                event
+            });
+
+            if !res.as_ref().is_ok_and(|event| event.paths.is_empty()) {
+                tx.send((res, time::Instant::now())).ok();
+            }
+        };
+
+        let mut watcher: Box<dyn Watcher + Send> = if poll {
+            let config = notify::Config::default()
+                .with_poll_interval(Duration::from_millis(args.poll_interval))
+                .with_follow_symlinks(false);
+
+            Box::new(PollWatcher::new(handler, config)?)
+        } else {
+            let config = notify::Config::default().with_follow_symlinks(false);
+            Box::new(RecommendedWatcher::new(handler, config)?)
+        };
+
+        watcher.watch(&dir, notify::RecursiveMode::NonRecursive)?;
+        watchers.push(watcher);
+    }
+
+    // A change found by both watchers would be recorded again once the other one reports it, which
+    // it could be after the rebuild that the first one triggered. Polling finds changes up to an
+    // interval after they're made, so the same change is reported within two of them.
+    let mut seen = (args.watch_mode == WatchMode::Hybrid).then(|| {
+        (
+            HashMap::new(),
+            Duration::from_millis(args.poll_interval) * 2,
+        )
+    });
+
+    let watched_guide = guide.clone();
+
+    let root_file = match &args.root_page {
+        Some(path) => Some(fs::canonicalize(path).await.map_err(|e| {
+            DocsServerError::Usage(format!(
+                "Could not read the root page `{}`: {e}",
+                path.display()
+            ))
+        })?),
+        None => None,
+    };
+
+    let root_page = {
+        let state = state.clone();
+        let packages = packages.clone();
+        let doc_dir = doc_dir(&target);
+        let index = doc_root.join(&doc_dir).join("index.html");
+        let all_targets = args.all_targets;
+
+        // Redirecting `/` to a target whose documentation isn't there would just 404, or loop if
+        // its name is empty, so an index of the targets is served instead. Whether it's there is
+        // checked on every request, since builds can leave it out or bring it back. So is whether
+        // a workspace member failed to build, which the index flags.
+        routing::get(move || async move {
+            let members = {
+                let state = state.read().unwrap();
+
+                if !state.ready {
+                    return Html(pages::building()).into_response();
+                }
+
+                state.members.clone()
+            };
+
+            if let Some(file) = root_file {
+                return match fs::read_to_string(&file).await {
+                    Ok(page) => Html(page).into_response(),
+                    Err(e) => {
+                        log::error!("Could not read the root page `{}`: {e}", file.display());
+                        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                    }
+                };
+            }
+
+            let documented = !all_targets
+                && members.iter().all(|member| member.failure.is_none())
+                && fs::try_exists(&index).await.unwrap_or(false);
+
+            match root_redirect(&doc_dir, documented) {
+                Some(path) => Redirect::temporary(&path).into_response(),
+                None => Html(pages::target_index(&packages, &members)).into_response(),
+            }
+        })
+    };
+
+    let docs: Router<()> = Router::new().route("/", root_page);
+
+    let redirect_status = if args.permanent_redirects {
+        StatusCode::MOVED_PERMANENTLY
+    } else {
+        StatusCode::FOUND
+    };
+
+    let docs = redirects.into_iter().fold(docs, |docs, (from, to)| {
+        docs.route(
+            &from,
+            routing::get(move || async move { (redirect_status, [(LOCATION, to)]) }),
+        )
+    });
+
+    let reload_ping = (args.reload_ping > 0).then(|| Duration::from_secs(args.reload_ping));
+
+    let docs = docs.route(
+        reload::PATH,
+        routing::get(reload::websocket).with_state((reloader.clone(), reload_ping)),
+    );
+
+    // Pages are listed from what's served, which snapshots take a copy of.
+    let served_dir = match &snapshots {
+        Some(snapshots) => Served::Snapshots(snapshots.clone()),
+        None => Served::Dir(doc_root.clone().into_std_path_buf()),
+    };
+
+    let docs = match snapshots {
+        Some(snapshots) => {
+            docs.fallback_service(routing::any(snapshot::serve).with_state(snapshots))
+        }
+        // Directories are served their `index.html`, and requests for them without a trailing
+        // slash, like links to `/crate/module`, are redirected to `/crate/module/` so that the
+        // page's relative links resolve.
+        None => docs.fallback_service(ServeDir::new(&doc_root)),
+    };
+
+    let docs = docs.layer(axum::middleware::from_fn_with_state(
+        state.clone(),
+        middleware::error_page,
+    ));
+
+    // Added after the error page so that the guide is still served while a build fails.
+    let docs = match guide {
+        Some(guide) => docs.nest_service(
+            guide::PATH,
+            ServeDir::new(guide.dir()).fallback(routing::get(guide::page).with_state(guide)),
+        ),
+        None => docs,
+    };
+
+    let docs = match &custom_css {
+        Some(css) => docs.route_service(middleware::CUSTOM_CSS_PATH, ServeFile::new(css)),
+        None => docs,
+    };
+
+    let docs = match &args.favicon {
+        Some(favicon) => docs.route_service(middleware::FAVICON_PATH, ServeFile::new(favicon)),
+        None => docs,
+    };
+
+    let docs = docs.route(middleware::HEALTH_PATH, routing::get(|| async { "OK" }));
+
+    let docs = match &args.public_url {
+        Some(url) if args.sitemap => {
+            let base = Arc::<str>::from(url.trim_end_matches('/'));
+
+            docs.route(
+                middleware::SITEMAP_PATH,
+                routing::get(sitemap).with_state((base, served_dir)),
+            )
+        }
+        _ => docs,
+    };
+
+    let docs = match debug_config {
+        Some(config) => docs.route(
+            middleware::DEBUG_CONFIG_PATH,
+            routing::get(move || {
+                let config = config.clone();
+                async move { axum::Json(config) }
+            }),
+        ),
+        None => docs,
+    };
+
+    let docs = match remote_rebuilds {
+        Some(handle) => docs.route(
+            middleware::REBUILD_PATH,
+            routing::post(remote_rebuild)
+                .with_state((handle, args.rebuild_token.as_deref().map(Arc::from))),
+        ),
+        None => docs,
+    };
+
+    let page_options = PageOptions {
+        preserve_scroll: !args.no_preserve_scroll,
+        smart_reload: args.smart_reload,
+        favicon: args.favicon.is_some(),
+        custom_css: args.custom_css.is_some(),
+        title_suffix: args.title_suffix.clone(),
+        default_theme: args.default_theme.clone(),
+    };
+
+    let docs = docs.layer(axum::middleware::from_fn_with_state(
+        (reloader, page_options),
+        middleware::rewrite_html,
+    ));
+
+    let docs = docs.layer(axum::middleware::from_fn(middleware::cache_files));
+    let docs = docs.layer(axum::middleware::from_fn(middleware::asset_types));
+
+    let docs = match args.wait_for_build {
+        Some(timeout) => docs.layer(axum::middleware::from_fn_with_state(
+            (building_rx, Duration::from_millis(timeout)),
+            middleware::wait_for_build,
+        )),
+        None => docs,
+    };
+
+    let docs = if args.compress {
+        // Only raster images are left out, since SVGs are text that compresses well.
+        let compressible = SizeAbove::new(args.min_compress_size)
+            .and(NotForContentType::GRPC)
+            .and(NotForContentType::SSE)
+            .and(NotForContentType::const_new("image/png"))
+            .and(NotForContentType::const_new("image/jpeg"))
+            .and(NotForContentType::const_new("image/gif"))
+            .and(NotForContentType::const_new("image/webp"))
+            .and(NotForContentType::const_new("image/avif"))
+            .and(NotForContentType::const_new("font/woff"));
+
+        docs.layer(CompressionLayer::new().compress_when(compressible))
+    } else {
+        docs
+    };
+
+    let strip_prefix = args
+        .strip_prefix
+        .as_deref()
+        .map(|prefix| prefix.trim_matches('/'))
+        .filter(|prefix| !prefix.is_empty())
+        .map(|prefix| Arc::from(format!("/{prefix}")));
+
+    let docs =
+        axum::middleware::from_fn_with_state(strip_prefix, middleware::strip_prefix).layer(docs);
+
+    // The initial build only starts once the server is serving, to show that it's running,
+    // unless the server only starts once it succeeds with `--fail-fast`.
+    let start = async move {
+        // How many builds failed in a row, to back off from with `--rebuild-backoff`.
+        let mut failures = 0;
+
+        if builder.build(BuildTrigger::Startup).await.is_err() {
+            failures += 1;
+
+            if args.fail_fast {
+                return Err(DocsServerError::Build);
+            }
+
+            log::warn!("The initial build failed! Serving its error until a build succeeds.");
+        }
+
+        let watch = async move {
+            // Moved into the future so that it keeps watching for as long as it runs.
+            let _watchers = watchers;
+            let mut comment_changes = scanning.await.unwrap_or_default();
+            let mut last_build: Option<time::Instant> = None;
+            let grace_end = time::Instant::now() + startup_grace;
+            // When the sources last changed during the warmup, which starts once the grace ends.
+            let mut quiet_since = grace_end;
+            let mut warm = warmup_quiet.is_none();
+
+            loop {
+                // Once there are no watchers, like with `--no-watch`, only requested rebuilds are
+                // waited for.
+                let event = tokio::select! {
+                    Some(event) = rx.recv() => event,
+                    Some(request) = rebuilds.recv() => {
+                        // Every request made before the rebuild starts is answered by it.
+                        let mut requests = vec![request];
+
+                        while let Ok(request) = rebuilds.try_recv() {
+                            requests.push(request);
+                        }
+
+                        log::info!("A rebuild was requested, recompiling...");
+                        let result = builder.build(BuildTrigger::Requested).await;
+                        failures = if result.is_ok() { 0 } else { failures + 1 };
+
+                        for request in requests {
+                            request.send(result.clone()).ok();
+                        }
+
+                        continue;
+                    }
+                    else => break,
+                };
+
+                // Changes reported during the grace period, including those queued during the initial
+                // build, are dropped.
+                if time::Instant::now() < grace_end {
+                    continue;
+                }
+
+                let mut changes = BTreeMap::new();
+                record_event(
+                    event,
+                    &mut changes,
+                    &root_canonical,
+                    &target_canonical,
+                    &ignored,
+                    seen.as_mut(),
+                )
+                .await;
+
+                // Every event arriving before the debounce window passes without any others is part
+                // of the same rebuild. Once files are created, removed, or renamed, the window is
+                // `--debounce-structural` instead of `--debounce-modify`.
+                loop {
+                    let debounce = if changes.values().any(|change| *change != "changed") {
+                        debounce_structural
+                    } else {
+                        debounce_modify
+                    };
+
+                    match time::timeout(debounce, rx.recv()).await {
+                        Ok(Some(event)) => {
+                            record_event(
+                                event,
+                                &mut changes,
+                                &root_canonical,
+                                &target_canonical,
+                                &ignored,
+                                seen.as_mut(),
+                            )
+                            .await
+                        }
+                        _ => break,
+                    }
+                }
+
+                if changes.is_empty() {
+                    continue;
+                }
+
+                // Changes are dropped until the sources go quiet for `--warmup-quiet`, with every
+                // one made meanwhile restarting the wait.
+                if let Some(quiet) = warmup_quiet
+                    && !warm
+                {
+                    let now = time::Instant::now();
+
+                    if now < quiet_since + quiet {
+                        quiet_since = now;
+                        continue;
+                    }
+
+                    warm = true;
+                }
+
+                // Rebuilding too soon after the last one is deferred, with the changes made meanwhile
+                // joining it.
+                if let Some(last_build) = last_build {
+                    let interval = rebuild_interval(min_rebuild_interval, failures, backoff);
+                    let deadline = last_build + interval;
+
+                    if interval > min_rebuild_interval && deadline > time::Instant::now() {
+                        log::info!(
+                            "{failures} builds failed in a row, waiting until {:.1}s after the \
+                             last one to rebuild...",
+                            interval.as_secs_f64()
+                        );
+                    }
+
+                    while let Ok(Some(event)) = time::timeout_at(deadline, rx.recv()).await {
+                        record_event(
+                            event,
+                            &mut changes,
+                            &root_canonical,
+                            &target_canonical,
+                            &ignored,
+                            seen.as_mut(),
+                        )
+                        .await;
+                    }
+                }
+
+                last_build = Some(time::Instant::now());
+
+                let description = match changes.len() {
+                    1 => {
+                        let (path, change) = changes.first_key_value().unwrap();
+                        format!("{} {change}", path.display())
+                    }
+                    count => {
+                        let mut paths: Vec<_> = changes
+                            .keys()
+                            .take(LOGGED_CHANGES)
+                            .map(|path| format!("`{}`", path.display()))
+                            .collect();
+
+                        if count > LOGGED_CHANGES {
+                            paths.push(format!("{} more", count - LOGGED_CHANGES));
+                        }
+
+                        format!("{count} files changed ({})", list(&paths))
+                    }
+                };
+
+                if let Some(css) = &css_path
+                    && changes.contains_key(css)
+                {
+                    if changes.len() == 1 {
+                        log::info!("{description}, restyling the pages...");
+                        builder.reloader.restyle();
+                        continue;
+                    }
+
+                    // Pages are reloaded after the build anyway, which loads the new styles.
+                    changes.remove(css);
+                }
+
+                let in_guide =
+                    |path: &PathBuf| guide_path.as_ref().is_some_and(|g| path.starts_with(g));
+
+                if let Some(guide) = &watched_guide
+                    && changes.keys().any(in_guide)
+                {
+                    if changes.keys().all(in_guide) {
+                        log::info!("{description}, rendering the guide...");
+                        guide.render();
+                        builder.reloader.reload();
+                        continue;
+                    }
+
+                    guide.render();
+                }
+
+                let only_comments = comment_changes.only_comments(&changes);
+                let changed: Vec<_> = changes.into_keys().collect();
+
+                let trigger = if only_comments {
+                    log::info!("{description}, only in comments, recompiling...");
+                    BuildTrigger::Comments(&changed)
+                } else {
+                    log::info!("{description}, recompiling...");
+                    BuildTrigger::Changes(&changed)
+                };
+
+                if builder.build(trigger).await.is_ok() {
+                    failures = 0;
+
+                    if let Some((url, path)) = &reopen_url
+                        && let Some(url) = url.get()
+                    {
+                        open_browser(&format!("{url}{path}"));
+                    }
+                } else {
+                    failures += 1;
+                }
+            }
+        };
+
+        Ok(vec![Box::pin(watch) as Watch])
+    };
+
+    Ok(Site {
+        docs: Router::new().fallback_service(docs),
+        start: Box::pin(start),
+        temp_target,
+        state,
+    })
+}
+
+/// Prepares rebuilds of a package to only document the targets whose sources changed, if it has
+/// more than one that's documented.
+///
+/// Binaries named like the library are left out, since cargo only documents the library then.
+///
+/// Arguments:
+/// * `package` - The documented package.
+/// * `all_targets` - Whether examples are documented too, with `--all-targets`.
+/// * `root` - The canonical path of the crate's root.
+///
+/// Returns:
+/// [`Option<TargetScope>`] -> The scope, or [`None`] if there's nothing to narrow rebuilds to.
+fn target_scope(package: &Package, all_targets: bool, root: &Path) -> Option<TargetScope> {
+    let lib = package.targets.iter().find(|t| t.is_lib());
+
+    let targets: Vec<_> = package
+        .targets
+        .iter()
+        .map(|t| {
+            let selection = if t.is_lib() {
+                Some(vec!["--lib".to_string()])
+            } else if t.is_bin() && lib.is_none_or(|lib| lib.name != t.name) {
+                Some(vec!["--bin".to_string(), t.name.clone()])
+            } else if t.is_example() && all_targets {
+                Some(vec!["--example".to_string(), t.name.clone()])
+            } else {
+                None
+            };
+
+            (t.src_path.clone().into_std_path_buf(), selection)
+        })
+        .collect();
+
+    let documented = targets.iter().filter(|(_, s)| s.is_some()).count();
+    (documented > 1).then(|| TargetScope::new(root.to_owned(), targets))
+}
+
+/// Checks that the git repository a directory is in has no uncommitted changes, for
+/// `--require-clean`.
+///
+/// Directories that aren't in a git repository are only warned about. If git can't be run, or
+/// fails for another reason, like not trusting a repository owned by another user, whether there
+/// are changes can't be told, so that's an error too.
+///
+/// Arguments:
+/// * `root` - The directory to check.
+///
+/// Returns:
+/// [`Result<(), DocsServerError>`] -> An error listing the changes if there are any, or saying why
+/// they couldn't be listed.
+async fn require_clean(root: &Path) -> Result<(), DocsServerError> {
+    // Git's messages are only matched in English.
+    let git = |args: &'static [&'static str]| {
+        tokio::process::Command::new("git")
+            .args(args)
+            .current_dir(root)
+            .env("LC_ALL", "C")
+            .output()
+    };
+
+    let failed = |output: &std::process::Output| {
+        DocsServerError::Usage(format!(
+            "Could not tell whether the repository has uncommitted changes for \
+             `--require-clean`: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    };
+
+    let inside = git(&["rev-parse", "--is-inside-work-tree"])
+        .await
+        .map_err(|e| {
+            DocsServerError::Usage(format!(
+                "Could not run git to check for uncommitted changes for `--require-clean`: {e}"
+            ))
+        })?;
+
+    if !inside.status.success() {
+        if !String::from_utf8_lossy(&inside.stderr).contains("not a git repository") {
+            return Err(failed(&inside));
+        }
+
+        log::warn!(
+            "`{}` isn't in a git repository, so whether it has uncommitted changes isn't checked.",
+            root.display()
+        );
+        return Ok(());
+    }
+
+    let output = git(&["status", "--porcelain"]).await?;
+
+    if !output.status.success() {
+        return Err(failed(&output));
+    }
+
+    let changes = String::from_utf8_lossy(&output.stdout);
+
+    if changes.trim().is_empty() {
+        return Ok(());
+    }
+
+    Err(DocsServerError::Usage(format!(
+        "The repository has uncommitted changes, and `--require-clean` was passed! Commit or \
+         stash them first:\n{}",
+        changes.trim_end()
+    )))
+}
+
+/// The least time between the start of two rebuilds, backing off from failed ones.
+///
+/// Arguments:
+/// * `min` - The least time between rebuilds, given with `--min-rebuild-interval`.
+/// * `failures` - How many builds failed in a row.
+/// * `backoff` - How many failures in a row to back off after, and the most time to back off for,
+///   if backing off.
+///
+/// Returns:
+/// [`Duration`] -> The least time to wait since the start of the last rebuild.
+fn rebuild_interval(min: Duration, failures: u32, backoff: Option<(u32, Duration)>) -> Duration {
+    let Some((threshold, max)) = backoff else {
+        return min;
+    };
+
+    if failures == 0 || failures < threshold {
+        return min;
+    }
+
+    let start = min.max(Duration::from_secs(1));
+    let doublings = (failures - threshold).min(31);
+
+    start.saturating_mul(1 << doublings).min(max).max(min)
+}
+
+/// Watches every path recursively.
+///
+/// Arguments:
+/// * `watcher` - The watcher to watch them with.
+/// * `paths` - The paths to watch.
+///
+/// Returns:
+/// [`notify::Result<()>`] -> Why a path couldn't be watched, if one couldn't.
+fn watch_all(watcher: &mut impl Watcher, paths: &BTreeSet<PathBuf>) -> notify::Result<()> {
+    for path in paths {
+        watcher.watch(path, notify::RecursiveMode::Recursive)?;
+    }
+
+    Ok(())
+}
+
+/// Describes where a package that isn't checked out locally comes from, for logs and errors.
+///
+/// Arguments:
+/// * `source` - The package's source.
+///
+/// Returns:
+/// [`String`] -> The description, like `crates.io`.
+fn source_name(source: &Source) -> String {
+    if source.is_crates_io() {
+        "crates.io".to_string()
+    } else if source.repr.starts_with("git+") {
+        "a git repository".to_string()
+    } else {
+        format!("`{source}`")
+    }
+}
+
+/// Finds the packages that the packages depend on through a path, directly or not.
+///
+/// Development dependencies are left out, since they don't change the documentation.
+///
+/// Arguments:
+/// * `metadata` - The workspace's metadata.
+/// * `packages` - The documented packages.
+///
+/// Returns:
+/// [`Vec<&Package>`] -> The path dependencies, other than the documented packages themselves.
+fn path_dependencies<'a>(metadata: &'a Metadata, packages: &[Package]) -> Vec<&'a Package> {
+    let mut found: Vec<&Package> = vec![];
+    let mut pending: Vec<&Package> = packages.iter().collect();
+
+    while let Some(package) = pending.pop() {
+        let paths = package
+            .dependencies
+            .iter()
+            .filter(|dep| dep.kind != DependencyKind::Development)
+            .filter_map(|dep| dep.path.as_ref());
+
+        for path in paths {
+            let Some(dependency) = metadata
+                .packages
+                .iter()
+                .find(|p| p.manifest_path.parent() == Some(path.as_path()))
+            else {
+                continue;
+            };
+
+            let known = |p: &&Package| p.id == dependency.id;
+
+            if !packages.iter().any(|p| known(&p)) && !found.iter().any(known) {
+                found.push(dependency);
+                pending.push(dependency);
+            }
+        }
+    }
+
+    found
+}
+
+/// Resolves the directories given with `--watch-only`, checking that they're within the sources.
+///
+/// Arguments:
+/// * `paths` - The directories, relative to the crate's root.
+/// * `root` - The crate's root.
+/// * `sources` - The source directories that would be watched otherwise.
+///
+/// Returns:
+/// [`Result<BTreeSet<PathBuf>, DocsServerError>`] -> The directories to watch instead, or why one
+/// of them can't be.
+async fn watched_subset(
+    paths: &[PathBuf],
+    root: &Path,
+    sources: &BTreeSet<PathBuf>,
+) -> Result<BTreeSet<PathBuf>, DocsServerError> {
+    let mut canonical_sources = vec![];
+
+    for source in sources {
+        // Sources that don't exist, like a package without `src/`, can't contain anything.
+        if let Ok(source) = fs::canonicalize(source).await {
+            canonical_sources.push(source);
+        }
+    }
+
+    let mut subset = BTreeSet::new();
+
+    for path in paths {
+        let canonical = fs::canonicalize(root.join(path)).await.map_err(|e| {
+            DocsServerError::Usage(format!("Could not watch `{}`: {e}", path.display()))
+        })?;
+
+        if !canonical_sources
+            .iter()
+            .any(|source| canonical.starts_with(source))
+        {
+            return Err(DocsServerError::Usage(format!(
+                "Could not watch `{}`, it's not within the sources being documented!",
+                path.display()
+            )));
+        }
+
+        subset.insert(canonical);
+    }
+
+    Ok(subset)
+}
+
+/// Looks for where a build wrote a target's documentation to, when it's not where it's served from.
+///
+/// The documentation directory at the root of the target directory, and the ones of every target
+/// triple's directory in it, are looked at.
+///
+/// Arguments:
+/// * `target_directory` - The target directory.
+/// * `doc_dir` - The directory the target is documented at.
+/// * `since` - When the build started, to tell fresh documentation from that left by other builds.
+///
+/// Returns:
+/// [`Option<PathBuf>`] -> The documentation directory the target's index was written to since,
+/// if any.
+async fn written_docs(
+    target_directory: &Path,
+    doc_dir: &str,
+    since: SystemTime,
+) -> Option<PathBuf> {
+    let mut candidates = vec![target_directory.join("doc")];
+
+    if let Ok(mut entries) = fs::read_dir(target_directory).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            candidates.push(entry.path().join("doc"));
+        }
+    }
+
+    for candidate in candidates {
+        let modified = fs::metadata(candidate.join(doc_dir).join("index.html"))
+            .await
+            .and_then(|metadata| metadata.modified());
+
+        if modified.is_ok_and(|modified| modified >= since) {
+            return Some(candidate);
+        }
+    }
+
+    None
+}
+
+/// Parses the redirects given with `--redirect`, checking that they can be served.
+///
+/// Arguments:
+/// * `redirects` - The redirects, like `/from=/to`.
+///
+/// Returns:
+/// [`Result<BTreeMap<String, String>, DocsServerError>`] -> The path every redirect is served at,
+/// with where it redirects to, or why one of them can't be served.
+fn redirect_map(redirects: &[String]) -> Result<BTreeMap<String, String>, DocsServerError> {
+    let mut map = BTreeMap::new();
+
+    for redirect in redirects {
+        let Some((from, to)) = redirect.split_once('=') else {
+            return Err(DocsServerError::Usage(format!(
+                "Redirects are given like `/from=/to`, not `{redirect}`!"
+            )));
+        };
+
+        // Paths with braces, or segments starting with `:` or `*`, would be routed as parameters,
+        // and the rest are served already.
+        if !from.starts_with('/')
+            || from.contains(['{', '}'])
+            || from
+                .split('/')
+                .any(|segment| segment.starts_with([':', '*']))
+            || middleware::RESERVED_PATHS.contains(&from)
+            || from
+                .strip_prefix(guide::PATH)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        {
+            return Err(DocsServerError::Usage(format!(
+                "Could not redirect `{from}`, redirects must be from a path of the documentation!"
+            )));
+        }
+
+        if to.is_empty() {
+            return Err(DocsServerError::Usage(format!(
+                "The redirect from `{from}` doesn't say where to!"
+            )));
+        }
+
+        if map.insert(from.to_string(), to.to_string()).is_some() {
+            return Err(DocsServerError::Usage(format!(
+                "There's more than one redirect from `{from}`!"
+            )));
+        }
+    }
+
+    for from in map.keys() {
+        let mut visited = BTreeSet::from([from]);
+        let mut current = from;
+
+        while let Some(to) = map.get(current) {
+            if !visited.insert(to) {
+                return Err(DocsServerError::Usage(format!(
+                    "The redirect from `{from}` loops back through `{to}`!"
+                )));
+            }
+
+            current = to;
+        }
+    }
+
+    Ok(map)
+}
+
+/// Reads the names of the packages listed with `--packages-from`.
+///
+/// Arguments:
+/// * `path` - The file listing them, or `-` for the standard input.
+///
+/// Returns:
+/// [`Result<Vec<String>, DocsServerError>`] -> The names, without empty lines, or why they
+/// couldn't be read.
+async fn listed_packages(path: &Path) -> Result<Vec<String>, DocsServerError> {
+    let listed = if path == Path::new("-") {
+        tokio::task::spawn_blocking(|| std::io::read_to_string(std::io::stdin()))
+            .await
+            .map_err(std::io::Error::other)
+            .flatten()
+    } else {
+        fs::read_to_string(path).await
+    };
+
+    let listed = listed.map_err(|e| {
+        DocsServerError::Usage(format!(
+            "Could not read the packages listed in `{}`: {e}",
+            path.display()
+        ))
+    })?;
+
+    Ok(listed
+        .lines()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Takes the listening sockets passed down by the process that started the server.
+///
+/// Those are the file descriptors given with `--listen-fd`, or otherwise the ones passed through
+/// systemd's socket activation, if any.
+///
+/// Arguments:
+/// * `fds` - The file descriptors given with `--listen-fd`.
+///
+/// Returns:
+/// [`Result<Vec<std::net::TcpListener>, DocsServerError>`] -> The sockets, or why they couldn't
+/// be taken.
+#[cfg(unix)]
+fn inherited_listeners(fds: &[i32]) -> Result<Vec<std::net::TcpListener>, DocsServerError> {
+    use std::os::fd::FromRawFd;
+
+    // systemd passes sockets as the file descriptors starting at 3, and tells how many there are
+    // to the process they're meant for.
+    const SD_LISTEN_FDS_START: i32 = 3;
+
+    let fds = if fds.is_empty() {
+        let for_us =
+            std::env::var("LISTEN_PID").is_ok_and(|pid| pid == std::process::id().to_string());
+        let count: i32 = std::env::var("LISTEN_FDS")
+            .ok()
+            .and_then(|count| count.parse().ok())
+            .filter(|_| for_us)
+            .unwrap_or(0);
+
+        // Like `sd_listen_fds`, the variables are unset so that the processes the server starts,
+        // like cargo, don't take the sockets for theirs.
+        // SAFETY: They're unset before the server starts any task or thread that could read the
+        // environment.
+        unsafe {
+            std::env::remove_var("LISTEN_FDS");
+            std::env::remove_var("LISTEN_PID");
+            std::env::remove_var("LISTEN_FDNAMES");
+        }
+
+        (SD_LISTEN_FDS_START..SD_LISTEN_FDS_START + count).collect()
+    } else {
+        fds.to_vec()
+    };
+
+    fds.into_iter()
+        .map(|fd| {
+            // The sockets are closed in the processes the server starts, which would otherwise
+            // keep them open after it exits.
+            // SAFETY: `fcntl` only reads and sets the flags of the file descriptor.
+            let closed_on_exec = unsafe {
+                let flags = libc::fcntl(fd, libc::F_GETFD);
+                flags != -1 && libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC) != -1
+            };
+
+            if !closed_on_exec {
+                return Err(DocsServerError::Usage(format!(
+                    "The file descriptor {fd} isn't a listening TCP socket: {}",
+                    std::io::Error::last_os_error()
+                )));
+            }
+
+            // SAFETY: The file descriptor was passed down to this process to listen on, and
+            // nothing else in it uses it.
+            let listener = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+
+            match listener.local_addr() {
+                Ok(_) => Ok(listener),
+                Err(e) => Err(DocsServerError::Usage(format!(
+                    "The file descriptor {fd} isn't a listening TCP socket: {e}"
+                ))),
+            }
+        })
+        .collect()
+}
+
+/// Takes the listening sockets passed down by the process that started the server, which isn't
+/// supported outside of Unix.
+///
+/// Arguments:
+/// * `fds` - The file descriptors given with `--listen-fd`.
+///
+/// Returns:
+/// [`Result<Vec<std::net::TcpListener>, DocsServerError>`] -> No sockets, or an error if some
+/// were given.
+#[cfg(not(unix))]
+fn inherited_listeners(fds: &[i32]) -> Result<Vec<std::net::TcpListener>, DocsServerError> {
+    if fds.is_empty() {
+        Ok(vec![])
+    } else {
+        Err(DocsServerError::Usage(
+            "Listening on inherited file descriptors with `--listen-fd` is only supported on Unix!"
+                .to_string(),
+        ))
+    }
+}
+
+/// Binds a Unix socket, replacing a socket left behind at its path.
+///
+/// Arguments:
+/// * `path` - The path of the socket.
+///
+/// Returns:
+/// [`Result<UnixListener, DocsServerError>`] -> The socket, or why it couldn't be bound.
+#[cfg(unix)]
+fn bind_unix(path: &Path) -> Result<tokio::net::UnixListener, DocsServerError> {
+    use std::os::unix::fs::FileTypeExt;
+
+    // Only sockets are removed, so that a mistyped path doesn't delete some other file.
+    if std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+
+    tokio::net::UnixListener::bind(path).map_err(|source| DocsServerError::BindUnix {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+/// Reads the files of patterns given with `--ignore-file`.
+///
+/// Files that are missing, or patterns that can't be parsed, are warned about and skipped.
+///
+/// Arguments:
+/// * `files` - The paths of the files.
+///
+/// Returns:
+/// [`Vec<Gitignore>`] -> The patterns of each file that could be read.
+fn ignore_files(files: &[PathBuf]) -> Vec<Gitignore> {
+    let mut ignored = vec![];
+
+    for file in files {
+        let file = match std::fs::canonicalize(file) {
+            Ok(file) => file,
+            Err(e) => {
+                log::warn!("Could not read the ignore file `{}`: {e}", file.display());
+                continue;
+            }
+        };
+
+        let mut builder = GitignoreBuilder::new(file.parent().unwrap_or(&file));
+
+        if let Some(e) = builder.add(&file) {
+            log::warn!("Could not read every pattern of `{}`: {e}", file.display());
+        }
+
+        match builder.build() {
+            Ok(ignore) => ignored.push(ignore),
+            Err(e) => log::warn!("Could not read the ignore file `{}`: {e}", file.display()),
+        }
+    }
+
+    ignored
+}
+
+/// The changes recorded with `--watch-mode hybrid`, by path, as the state the files were in and
+/// when they were recorded, and how long after one watcher the other can report the same change.
+type SeenChanges = (
+    HashMap<PathBuf, (Option<(SystemTime, u64)>, time::Instant)>,
+    Duration,
+);
+
+/// Records the paths affected by a watcher event, alongside what happened to them.
+///
+/// Paths are made relative to `root`. Events that don't create, modify, or remove files are
+/// ignored, and so are paths in the target directory or matched by an ignore file.
+///
+/// Arguments:
+/// * `event` - The event received from the watcher, with when it was received.
+/// * `changes` - The changes recorded so far, which the event's paths are added to.
+/// * `root` - The canonical path of the crate's root.
+/// * `target` - The canonical path of the target directory, which builds write to.
+/// * `ignored` - The patterns of the files given with `--ignore-file`.
+/// * `seen` - When more than one watcher reports changes, the modification times and lengths
+///   paths had when they were last recorded, if they existed, and when they were reported, along with how
+///   long the other watcher can take to report the same change. Paths that didn't change since
+///   are left out if they're reported again within that time.
+async fn record_event(
+    (res, received): (notify::Result<Event>, time::Instant),
+    changes: &mut BTreeMap<PathBuf, &'static str>,
+    root: &Path,
+    target: &Path,
+    ignored: &[Gitignore],
+    mut seen: Option<&mut SeenChanges>,
+) {
+    let event = match res {
+        Ok(event) => event,
+        Err(e) => {
+            log::error!("Watch error: {e:?}");
+            return;
+        }
+    };
+
+    let change = match event.kind {
+        EventKind::Create(_) => "created",
+        EventKind::Modify(ModifyKind::Name(_)) => "renamed",
+        EventKind::Modify(_) => "changed",
+        EventKind::Remove(_) => "removed",
+        _ => return,
+    };
+
+    for path in event.paths {
+        if path.starts_with(target) {
+            continue;
+        }
+
+        // Read off the runtime's threads, since the disk can be slow to answer.
+        let metadata = fs::metadata(&path).await.ok();
+
+        let is_ignored = |ignore: &Gitignore| {
+            path.starts_with(ignore.path())
+                && ignore
+                    .matched_path_or_any_parents(
+                        &path,
+                        metadata.as_ref().is_some_and(|m| m.is_dir()),
+                    )
+                    .is_ignore()
+        };
+
+        if ignored.iter().any(is_ignored) {
+            continue;
+        }
+
+        if let Some((seen, window)) = seen.as_mut() {
+            let state = metadata
+                .as_ref()
+                .and_then(|m| Some((m.modified().ok()?, m.len())));
+            if let Some((last, at)) = seen.insert(path.clone(), (state, received))
+                && last == state
+                && received.saturating_duration_since(at) < *window
+            {
+                continue;
+            }
+        }
+
+        let relative_path = pathdiff::diff_paths(&path, root).unwrap_or(path);
+        changes.insert(relative_path, change);
+    }
+}
+
+/// Finds what the symlinks in watched directories point to, like shared modules linked into
+/// several crates' `src/`, following the symlinks in those too.
+///
+/// Targets inside a watched path, or inside another target, are left out, and so are broken
+/// symlinks.
+///
+/// Arguments:
+/// * `paths` - The watched paths.
+///
+/// Returns:
+/// [`BTreeSet<PathBuf>`] -> The canonical paths of the targets to watch too.
+fn symlink_targets(paths: &BTreeSet<PathBuf>) -> BTreeSet<PathBuf> {
+    let watched: Vec<_> = paths
+        .iter()
+        .map(|path| std::fs::canonicalize(path).unwrap_or_else(|_| path.clone()))
+        .collect();
+
+    let mut targets = BTreeSet::<PathBuf>::new();
+    let mut pending = watched.clone();
+
+    while let Some(dir) = pending.pop() {
+        let links = WalkDir::new(&dir)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path_is_symlink());
+
+        for link in links {
+            let Ok(target) = std::fs::canonicalize(link.path()) else {
+                continue;
+            };
+
+            if watched
+                .iter()
+                .chain(&targets)
+                .any(|path| target.starts_with(path))
+            {
+                continue;
+            }
+
+            targets.retain(|path| !path.starts_with(&target));
+
+            if target.is_dir() {
+                pending.push(target.clone());
+            }
+
+            targets.insert(target);
+        }
+    }
+
+    targets
+}
+
+/// Finds every HTML file in a directory, recursively.
+///
+/// Arguments:
+/// * `dir` - The directory.
+///
+/// Returns:
+/// [`Vec<String>`] -> The paths of the files relative to the directory, with forward slashes,
+/// sorted.
+fn html_files(dir: &Path) -> Vec<String> {
+    WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| entry.path().extension().is_some_and(|v| v == "html"))
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(dir).ok()?;
+            let segments: Vec<_> = relative
+                .components()
+                .map(|v| v.as_os_str().to_string_lossy())
+                .collect();
+
+            Some(segments.join("/"))
+        })
+        .collect()
+}
+
+/// Whether a target's documentation is generated.
+///
+/// Arguments:
+/// * `target` - The target.
+/// * `all_targets` - Whether `--all-targets` is set, making examples documented too.
+///
+/// Returns:
+/// [`bool`] -> Whether the target is documented.
+fn is_documented(target: &Target, all_targets: bool) -> bool {
+    target.is_lib() || target.is_bin() || (all_targets && target.is_example())
+}
+
+/// The name of the directory rustdoc generates a target's documentation at.
+///
+/// Arguments:
+/// * `target` - The target.
+///
+/// Returns:
+/// [`String`] -> The directory's name, relative to the documentation's root.
+fn doc_dir(target: &Target) -> String {
+    target.name.replace('-', "_")
+}
+
+/// Where `/` redirects to, if it redirects to the documented target at all.
+///
+/// Arguments:
+/// * `doc_dir` - The directory of the target's documentation, from [`doc_dir`].
+/// * `documented` - Whether the target's documentation is there to redirect to.
+///
+/// Returns:
+/// [`Option<String>`] -> The path to redirect to, or [`None`] to serve an index of the targets,
+/// like when the target's name is empty and the redirect would loop back to `/`.
+fn root_redirect(doc_dir: &str, documented: bool) -> Option<String> {
+    (!doc_dir.is_empty() && documented).then(|| format!("/{doc_dir}/"))
+}
+
+/// The features of a package, except for the skipped ones and those that enable them.
+///
+/// `default` is left out too, since the features it enables are already listed on their own.
+///
+/// Arguments:
+/// * `package` - The package.
+/// * `skipped` - The names of the features not to enable.
+///
+/// Returns:
+/// [`Vec<String>`] -> The names of the features to enable.
+fn features_without(package: &Package, skipped: &[String]) -> Vec<String> {
+    let enables_skipped = |feature: &str| {
+        let mut pending = vec![feature];
+        let mut seen = BTreeSet::new();
+
+        while let Some(feature) = pending.pop() {
+            if skipped.iter().any(|skipped| skipped == feature) {
+                return true;
+            }
+
+            if !seen.insert(feature) {
+                continue;
+            }
+
+            // Values like `dep:name` and `name/feature` aren't features of the package itself.
+            if let Some(enabled) = package.features.get(feature) {
+                pending.extend(enabled.iter().map(String::as_str));
+            }
+        }
+
+        false
+    };
+
+    package
+        .features
+        .keys()
+        .filter(|feature| *feature != "default" && !enables_skipped(feature))
+        .cloned()
+        .collect()
+}
+
+/// Whether a target's crate is `#![no_std]`, going by the attributes at the root of its source.
+///
+/// Arguments:
+/// * `target` - The target.
+///
+/// Returns:
+/// [`bool`] -> Whether the crate doesn't link `std`, or `false` if its source couldn't be read.
+fn is_no_std(target: &Target) -> bool {
+    let Ok(source) = std::fs::read_to_string(&target.src_path) else {
+        return false;
+    };
+
+    source.lines().map(str::trim).any(|line| {
+        line.starts_with("#![no_std]")
+            || (line.starts_with("#![cfg_attr(") && line.contains("no_std"))
+    })
+}
+
+/// The themes rustdoc can show pages in, its own and the custom ones passed to it.
+///
+/// Custom themes are passed with `--theme <PATH>` through `RUSTDOCFLAGS`, and named after their file.
+///
+/// Returns:
+/// [`Vec<String>`] -> The names of the themes.
+fn rustdoc_themes() -> Vec<String> {
+    let mut themes = vec!["light".to_string(), "dark".to_string(), "ayu".to_string()];
+
+    let flags = std::env::var("RUSTDOCFLAGS").unwrap_or_default();
+    let mut flags = flags.split_whitespace();
+
+    while let Some(flag) = flags.next() {
+        let path = match flag.strip_prefix("--theme") {
+            Some("") => flags.next(),
+            Some(path) => path.strip_prefix('='),
+            None => None,
+        };
+
+        if let Some(stem) = path.and_then(|path| Path::new(path).file_stem()) {
+            themes.push(stem.to_string_lossy().into_owned());
+        }
+    }
+
+    themes
+}
+
+/// The URL a browser can open to reach the server at an address.
+///
+/// Arguments:
+/// * `address` - The address the server is bound to.
+///
+/// Returns:
+/// [`String`] -> The URL to open.
+fn openable_address(address: &SocketAddr) -> String {
+    // Browsers can't open wildcard addresses like `0.0.0.0` or `[::]`.
+    if address.ip().is_unspecified() {
+        format!("http://localhost:{}/", address.port())
+    } else {
+        format!("http://{address}/")
+    }
+}
+
+/// Checks a path given with `--open-path`, so that the URL opened is the one that was meant.
+///
+/// Arguments:
+/// * `path` - The path, relative to the server's root, with a `#fragment` if any.
+///
+/// Returns:
+/// [`Result<&str, DocsServerError>`] -> The path without its leading slashes, or why it can't be
+/// opened.
+fn open_path(path: &str) -> Result<&str, DocsServerError> {
+    if path.contains("://") {
+        return Err(DocsServerError::Usage(format!(
+            "`--open-path {path}` must be a path on the server, like `fixture/index.html`, not a \
+             URL!"
+        )));
+    }
+
+    // An encoded `%` followed by another escape, like `%2523` for `#`, is a sign of the path
+    // having been encoded twice, which would open a page that doesn't exist.
+    let twice_encoded = path.match_indices("%25").any(|(i, _)| {
+        let escape = path.as_bytes().get(i + 3..i + 5);
+        escape.is_some_and(|escape| escape.iter().all(u8::is_ascii_hexdigit))
+    });
+
+    if twice_encoded {
+        return Err(DocsServerError::Usage(format!(
+            "`--open-path {path}` looks percent-encoded twice! Pass it encoded once, like \
+             `struct.Server.html#method.run`."
+        )));
+    }
+
+    Ok(path.trim_start_matches('/'))
+}
+
+/// Announces the port the server was bound to, as `DOCS_SERVER_PORT=<PORT>` on the standard
+/// output, or as a `listening` event if events are written there.
+///
+/// Arguments:
+/// * `address` - The address the server was bound to.
+fn announce_port(address: &SocketAddr) {
+    if events::is_enabled() {
+        events::emit(serde_json::json!({
+            "event": "listening",
+            "address": address.to_string(),
+            "port": address.port(),
+        }));
+
+        return;
+    }
+
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "DOCS_SERVER_PORT={}", address.port()).ok();
+    stdout.flush().ok();
+}
+
+/// Opens a URL in the browser, logging whether it could. If it couldn't, the URL is printed to be
+/// opened manually, like from another machine.
+///
+/// Arguments:
+/// * `url` - The URL to open.
+fn open_browser(url: &str) {
+    match open::that(url) {
+        Ok(_) => log::info!("Opened documentation in browser!"),
+        Err(e) => {
+            log::error!("Failed to open documentation in browser: {e}");
+            log::warn!("Open it manually at:");
+            logging::highlight(url);
+        }
+    }
+}
+
+/// Turns an address the server is bound to into one it can be connected to at, like for
+/// [`is_reachable`].
+///
+/// Arguments:
+/// * `address` - The address the server is bound to.
+///
+/// Returns:
+/// [`SocketAddr`] -> The loopback address of the same family for wildcard addresses like
+/// `0.0.0.0`, or the address itself otherwise.
+fn probe_address(address: &SocketAddr) -> SocketAddr {
+    match address {
+        SocketAddr::V4(v4) if v4.ip().is_unspecified() => (Ipv4Addr::LOCALHOST, v4.port()).into(),
+        SocketAddr::V6(v6) if v6.ip().is_unspecified() => (Ipv6Addr::LOCALHOST, v6.port()).into(),
+        address => *address,
+    }
+}
+
+/// Probes whether the server accepts connections at an address, retrying a few times, so that
+/// the browser isn't opened before it can load the documentation.
+///
+/// Arguments:
+/// * `address` - The address to connect to.
+///
+/// Returns:
+/// [`bool`] -> Whether a connection succeeded.
+async fn is_reachable(address: SocketAddr) -> bool {
+    for attempt in 0..OPEN_PROBES {
+        if attempt > 0 {
+            time::sleep(OPEN_PROBE_INTERVAL).await;
+        }
+
+        let connect = tokio::net::TcpStream::connect(address);
+
+        if let Ok(Ok(_)) = time::timeout(OPEN_PROBE_INTERVAL, connect).await {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Picks the address the browser is most likely to reach the server at.
+///
+/// Loopback addresses are preferred, then wildcard ones, and then the first one given.
+///
+/// Arguments:
+/// * `addresses` - The addresses the server is bound to. Must not be empty.
+///
+/// Returns:
+/// [`&SocketAddr`] -> The most local address.
+fn most_local_address(addresses: &[SocketAddr]) -> &SocketAddr {
+    addresses
+        .iter()
+        .find(|address| address.ip().is_loopback())
+        .or_else(|| {
+            addresses
+                .iter()
+                .find(|address| address.ip().is_unspecified())
+        })
+        .unwrap_or(&addresses[0])
+}
+
+fn find_ideal_target(packages: &[Package]) -> Option<&Target> {
+    for package in packages {
+        for target in &package.targets {
+            if target.is_lib() {
+                return Some(target);
+            }
+        }
+    }
+
+    for package in packages {
+        for target in &package.targets {
+            if target.is_bin() {
+                return Some(target);
+            }
+        }
+    }
+
+    None
+}
+/// Formats items into a human-readable list.
+///
+/// For example,
+/// - `[1] => "1"`
+/// - `[1, 2] => "1 and 2"`
+/// - `[1, 2, 3] => "1, 2, and 3"`.
+/// - `[1, 2, 3, 4] => "1, 2, 3, and 4"`.
+///
+/// Arguments:
+/// * `items` - The items of the list.
+///
+/// Returns:
+/// [`String`] -> The formatted list.
+fn list<T>(items: &[T]) -> String
+where
+    T: Display,
+{
+    let mut string = String::new();
+
+    for (i, item) in items.iter().enumerate() {
+        let is_first = i == 0;
+        let is_penultimate = i + 2 == items.len();
+        let is_last = i + 1 == items.len();
+
+        match (is_first, is_penultimate, is_last) {
+            (false, false, false) => string.push_str(&format!("{item}, ")),
+            (false, false, true) => string.push_str(&item.to_string()),
+            (false, true, false) => string.push_str(&format!("{item}, and ")),
+            (false, true, true) => unreachable!(),
+            (true, false, false) => string.push_str(&format!("{item}, ")),
+            (true, false, true) => string.push_str(&item.to_string()),
+            (true, true, false) => string.push_str(&format!("{item} and ")),
+            (true, true, true) => unreachable!(),
+        }
+    }
+
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use super::{openable_address, root_redirect};
+
+    #[test]
+    fn opens_wildcards_on_localhost() {
+        let address = |address: &str| openable_address(&address.parse::<SocketAddr>().unwrap());
+
+        assert_eq!(address("[::]:8000"), "http://localhost:8000/");
+        assert_eq!(address("0.0.0.0:8000"), "http://localhost:8000/");
+        assert_eq!(address("127.0.0.1:8000"), "http://127.0.0.1:8000/");
+        assert_eq!(address("[::1]:8000"), "http://[::1]:8000/");
+    }
+
+    #[test]
+    fn never_redirects_to_empty_target_names() {
+        assert_eq!(root_redirect("", true), None);
+        assert_eq!(root_redirect("", false), None);
+        assert_eq!(root_redirect("fixture", false), None);
+        assert_eq!(root_redirect("fixture", true).as_deref(), Some("/fixture/"));
+    }
+}
@@ -0,0 +1,204 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::panic;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use colored::Colorize;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use time::OffsetDateTime;
+use time::macros::format_description;
+
+use crate::{LogFileMode, spinner};
+
+/// A logger writing pretty lines to the standard and error outputs.
+///
+/// The lines are formatted like `pretty-logging`'s, but each one is written while holding the
+/// output's lock only for that line so that spinners and other writers can share the terminal.
+struct Logger;
+
+static LOGGER: Logger = Logger;
+
+/// Whether every line is written to the error output, keeping the standard one free for events.
+static STDERR_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// The file every line is also written to, if any.
+static FILE: OnceLock<Mutex<File>> = OnceLock::new();
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let target = metadata.target();
+
+        // Only the binary's and the library's own logs, not those of dependencies.
+        target == "docs" || target == "docs_server" || target.starts_with("docs_server::")
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let message = record.args().to_string();
+
+            write_line(
+                record.level() == Level::Error,
+                &format_line(record.level().as_str(), &message, true),
+            );
+            write_file(&format_line(record.level().as_str(), &message, false));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Initializes the logger, and sets a panic hook that logs panics.
+///
+/// Arguments:
+/// * `filter` - The level filter for the logger.
+/// * `stderr_only` - Whether to write every line to the error output, not just errors.
+pub fn init(filter: LevelFilter, stderr_only: bool) {
+    STDERR_ONLY.store(stderr_only, Ordering::Relaxed);
+
+    log::set_logger(&LOGGER)
+        .map(|()| log::set_max_level(filter))
+        .unwrap();
+
+    panic::set_hook(Box::new(move |panic_info| {
+        if filter == LevelFilter::Off {
+            return;
+        }
+
+        let message = if let Some(s) = panic_info.payload().downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = panic_info.payload().downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "A panic occurred! Exitting...".to_string()
+        };
+
+        write_line(true, &format_line("PANIC", &message, true));
+        write_file(&format_line("PANIC", &message, false));
+    }));
+}
+
+/// Also writes every line logged from now on to a file, without colors.
+///
+/// Only lines that pass the logger's level filter are written, like on the terminal.
+///
+/// Arguments:
+/// * `path` - The file to write the lines to, created if it doesn't exist.
+/// * `mode` - What to do with the lines the file already has.
+///
+/// Returns:
+/// [`io::Result<()>`] -> Why the file couldn't be opened, if it couldn't.
+pub fn log_to_file(path: &Path, mode: LogFileMode) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.create(true);
+
+    match mode {
+        LogFileMode::Append => {
+            options.append(true);
+        }
+        LogFileMode::Truncate => {
+            options.write(true).truncate(true);
+        }
+        LogFileMode::Rotate => {
+            let mut old = path.as_os_str().to_owned();
+            old.push(".old");
+
+            match fs::rename(path, &old) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+
+            options.write(true).truncate(true);
+        }
+    }
+
+    FILE.set(Mutex::new(options.open(path)?)).ok();
+
+    Ok(())
+}
+
+/// Writes a line standing out from the logs to the error output, without their prefix so that it's
+/// easy to copy.
+///
+/// It's written whatever the level filter is, for things that mustn't get lost among the logs.
+///
+/// Arguments:
+/// * `text` - The text of the line.
+pub(crate) fn highlight(text: &str) {
+    write_line(true, &format!("\n    {}\n", text.bold()));
+    write_file(&format!("\n    {text}\n"));
+}
+
+/// Writes a line to the standard output, or to the error output if `error` is set or every line
+/// goes there.
+///
+/// A spinner being animated on the terminal is cleared first so that it doesn't get mixed with the
+/// line. It's drawn again on its next frame.
+///
+/// Arguments:
+/// * `error` - Whether to write the line to the error output.
+/// * `line` - The line to write.
+fn write_line(error: bool, line: &str) {
+    if spinner::is_spinning() {
+        let mut stderr = std::io::stderr().lock();
+        write!(stderr, "\r\x1b[2K").ok();
+        stderr.flush().ok();
+    }
+
+    if error || STDERR_ONLY.load(Ordering::Relaxed) {
+        let mut stderr = std::io::stderr().lock();
+        writeln!(stderr, "{line}").ok();
+        stderr.flush().ok();
+    } else {
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{line}").ok();
+        stdout.flush().ok();
+    }
+}
+
+/// Writes a line to the file given to [`log_to_file`], if there's one.
+///
+/// Arguments:
+/// * `line` - The line to write, without colors.
+fn write_file(line: &str) {
+    if let Some(file) = FILE.get() {
+        let mut file = file.lock().unwrap();
+        writeln!(file, "{line}").ok();
+    }
+}
+
+/// Formats a log line with the current time and the level it was logged at.
+///
+/// Arguments:
+/// * `level` - The name of the level, like `INFO`.
+/// * `message` - The logged message.
+/// * `color` - Whether to color the time and the level, for terminals.
+///
+/// Returns:
+/// [`String`] -> The formatted line.
+fn format_line(level: &str, message: &str, color: bool) -> String {
+    let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+
+    let format = format_description!(
+        "[day]/[month]/[year] at [hour]:[minute]:[second].[subsecond digits:2]"
+    );
+
+    let level_tag = format!("{:<7}", format!("[{level}]"));
+    let now = now.format(&format).unwrap();
+
+    if !color {
+        return format!("{now} {level_tag} {message}");
+    }
+
+    let level_tag = match level {
+        "TRACE" => level_tag.dimmed(),
+        "DEBUG" => level_tag.white(),
+        "INFO" => level_tag.blue(),
+        "WARN" => level_tag.yellow(),
+        _ => level_tag.red().bold(),
+    };
+
+    format!("{} {level_tag} {message}", now.dimmed())
+}
@@ -0,0 +1,594 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use axum::body::{self, Body};
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::header::{
+    CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+    LAST_MODIFIED, LOCATION,
+};
+use axum::http::{HeaderValue, Method, StatusCode, Uri};
+use axum::middleware::Next;
+use axum::response::{Html, IntoResponse, Response};
+
+use tokio::sync::watch;
+use tokio::time;
+
+use crate::build::BuildState;
+use crate::pages;
+use crate::reload::{self, Reloader};
+
+/// The path a custom favicon is served at.
+pub const FAVICON_PATH: &str = "/__favicon";
+
+/// The path the stylesheet given with `--custom-css` is served at.
+pub const CUSTOM_CSS_PATH: &str = "/__custom.css";
+
+/// The directory rustdoc writes its static files to, named after a hash of their contents.
+const HASHED_FILES_PATH: &str = "/static.files/";
+
+/// The content types of the files that rustdoc ships, like its fonts, that guessing from their
+/// extension gets wrong or outdated, by the extension they're served for.
+const ASSET_TYPES: &[(&str, &str)] = &[
+    (".woff2", "font/woff2"),
+    (".woff", "font/woff"),
+    (".ttf", "font/ttf"),
+    (".otf", "font/otf"),
+    (".wasm", "application/wasm"),
+];
+
+/// The path answering health checks, with `200 OK` for as long as the server runs.
+pub const HEALTH_PATH: &str = "/__health";
+
+/// The path the sitemap is served at, when `--sitemap` is set.
+pub const SITEMAP_PATH: &str = "/sitemap.xml";
+
+/// The path rebuilds are requested at with `POST`, when `--allow-remote-rebuild` is set.
+pub const REBUILD_PATH: &str = "/__rebuild";
+
+/// The path what the server's options resolved to is served at, when `--debug-endpoints` is set.
+pub const DEBUG_CONFIG_PATH: &str = "/__debug/config";
+
+/// The paths the server answers at itself, whatever the options, which redirects can't be from.
+///
+/// The guide's pages, under [`guide::PATH`](crate::guide::PATH), are served by prefix instead.
+pub const RESERVED_PATHS: &[&str] = &[
+    "/",
+    reload::PATH,
+    FAVICON_PATH,
+    CUSTOM_CSS_PATH,
+    HEALTH_PATH,
+    SITEMAP_PATH,
+    REBUILD_PATH,
+    DEBUG_CONFIG_PATH,
+];
+
+/// How [`rewrite_html`] changes every page.
+#[derive(Clone, Default)]
+pub struct PageOptions {
+    /// Whether the reload client keeps the scroll position of pages across reloads.
+    pub preserve_scroll: bool,
+    /// Whether the reload client swaps the content of pages in place when no pages were added or
+    /// removed, instead of reloading them.
+    pub smart_reload: bool,
+    /// Whether a custom favicon is served at [`FAVICON_PATH`], replacing rustdoc's.
+    pub favicon: bool,
+    /// Whether a custom stylesheet is served at [`CUSTOM_CSS_PATH`], loaded after rustdoc's.
+    pub custom_css: bool,
+    /// Text appended to the title of every page.
+    pub title_suffix: Option<String>,
+    /// The rustdoc theme to show pages in, regardless of the one picked in rustdoc's settings.
+    pub default_theme: Option<String>,
+}
+
+/// The prefix [`strip_prefix`] stripped from a request's path, stored in its extensions.
+///
+/// When prefixes are stripped more than once, like `/variant` after `/docs`, it holds all of them,
+/// like `/docs/variant`.
+#[derive(Clone)]
+pub struct StrippedPrefix(pub Arc<str>);
+
+/// Strips a prefix from the path of incoming requests before they're routed.
+///
+/// Nothing is stripped if no prefix was configured. Requests whose path doesn't start with the
+/// prefix are passed through unchanged. Redirects returned for stripped requests get the prefix
+/// added back to their `Location` so that they keep pointing through the proxy that added it.
+///
+/// Arguments:
+/// * `prefix` - The prefix to strip, if any, with a leading slash and without a trailing one.
+/// * `request` - The incoming request.
+/// * `next` - The rest of the middleware stack.
+///
+/// Returns:
+/// [`Response`] -> The response to the (possibly rewritten) request.
+pub async fn strip_prefix(
+    State(prefix): State<Option<Arc<str>>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(prefix) = prefix else {
+        return next.run(request).await;
+    };
+
+    let path = request.uri().path();
+
+    let stripped = if path == &*prefix {
+        Some("/".to_string())
+    } else {
+        path.strip_prefix(&*prefix)
+            .filter(|rest| rest.starts_with('/'))
+            .map(str::to_string)
+    };
+
+    let Some(stripped) = stripped else {
+        return next.run(request).await;
+    };
+
+    let path_and_query = match request.uri().query() {
+        Some(query) => format!("{stripped}?{query}"),
+        None => stripped,
+    };
+
+    let mut parts = request.uri().clone().into_parts();
+    parts.path_and_query = path_and_query.parse().ok();
+
+    if let Ok(uri) = Uri::from_parts(parts) {
+        *request.uri_mut() = uri;
+    }
+
+    let stripped = match request.extensions().get::<StrippedPrefix>() {
+        Some(outer) => Arc::from(format!("{}{prefix}", outer.0)),
+        None => prefix.clone(),
+    };
+
+    request.extensions_mut().insert(StrippedPrefix(stripped));
+
+    let mut response = next.run(request).await;
+
+    if let Some(location) = response
+        .headers()
+        .get(LOCATION)
+        .and_then(|v| v.to_str().ok())
+        && location.starts_with('/')
+        && let Ok(location) = HeaderValue::from_str(&format!("{prefix}{location}"))
+    {
+        response.headers_mut().insert(LOCATION, location);
+    }
+
+    response
+}
+
+/// Marks the build's error page, which [`rewrite_html`] injects the reload client into even though
+/// it's served as an error, so that it's reloaded once the build is fixed.
+#[derive(Clone)]
+struct ErrorPage;
+
+/// Serves the build's error in place of HTML pages while the last build is failing.
+///
+/// Other files, like scripts and stylesheets, are still served as usual.
+///
+/// Arguments:
+/// * `state` - The state of the documentation's builds.
+/// * `request` - The incoming request.
+/// * `next` - The rest of the middleware stack.
+///
+/// Returns:
+/// [`Response`] -> The error page, or the response to the request.
+pub async fn error_page(
+    State(state): State<Arc<RwLock<BuildState>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method();
+
+    if (method == Method::GET || method == Method::HEAD) && is_page(request.uri().path()) {
+        let failure = state.read().unwrap().failure.clone();
+
+        if let Some(failure) = failure {
+            let mut response = (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Html(pages::build_error(&failure)),
+            )
+                .into_response();
+
+            response.extensions_mut().insert(ErrorPage);
+            return response;
+        }
+    }
+
+    next.run(request).await
+}
+
+/// Rewrites the HTML pages served, injecting the reload client into them and applying the
+/// options.
+///
+/// Only successful responses to `GET` requests are rewritten, along with the build's error page,
+/// and `HEAD` ones get the same headers.
+/// The client is told to connect through the prefix stripped from the request, if any, and which
+/// build generation the page was served at. Since that changes with every build, pages are never
+/// revalidated against a cached copy.
+///
+/// Arguments:
+/// * `reloader` - The reloader to get the current build generation from.
+/// * `options` - How to change the pages.
+/// * `request` - The incoming request.
+/// * `next` - The rest of the middleware stack.
+///
+/// Returns:
+/// [`Response`] -> The response, rewritten if it's an HTML page.
+pub async fn rewrite_html(
+    State((reloader, options)): State<(Reloader, PageOptions)>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if is_page(request.uri().path()) {
+        request.headers_mut().remove(IF_MODIFIED_SINCE);
+        request.headers_mut().remove(IF_NONE_MATCH);
+    }
+
+    let prefix = request
+        .extensions()
+        .get::<StrippedPrefix>()
+        .map(|prefix| prefix.0.clone());
+    let method = request.method().clone();
+    let generation = reloader.generation();
+
+    let response = next.run(request).await;
+
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/html"));
+
+    let is_error_page = response.extensions().get::<ErrorPage>().is_some();
+
+    if !(method == Method::GET || method == Method::HEAD)
+        || !is_html
+        || !(response.status() == StatusCode::OK || is_error_page)
+    {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+
+    // Rewritten pages change in length, and with every build.
+    parts.headers.remove(CONTENT_LENGTH);
+    parts.headers.remove(LAST_MODIFIED);
+    parts
+        .headers
+        .insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+
+    // Answered with the headers the page would be served with, without rewriting a body that
+    // isn't sent.
+    if method == Method::HEAD {
+        return Response::from_parts(parts, Body::empty());
+    }
+
+    let Ok(bytes) = body::to_bytes(body, usize::MAX).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    let mut html = String::from_utf8_lossy(&bytes).into_owned();
+
+    let script = format!(
+        r#"<script data-path="{}{}" data-generation="{generation}" data-preserve-scroll="{}" data-smart-reload="{}">{}</script>"#,
+        prefix.as_deref().unwrap_or_default(),
+        reload::PATH,
+        options.preserve_scroll,
+        options.smart_reload,
+        reload::CLIENT
+    );
+
+    match html.rfind("</body>") {
+        Some(idx) => html.insert_str(idx, &script),
+        None => html.push_str(&script),
+    }
+
+    // rustdoc reads the theme from local storage while the head loads, so it's set before that.
+    if let Some(theme) = &options.default_theme
+        && let Some(idx) = html.find("<head>")
+    {
+        let script = format!(
+            r#"<script>try{{localStorage.setItem("rustdoc-theme",{});localStorage.setItem("rustdoc-use-system-theme","false")}}catch(e){{}}</script>"#,
+            serde_json::Value::from(theme.as_str())
+        );
+
+        html.insert_str(idx + "<head>".len(), &script);
+    }
+
+    if let Some(suffix) = &options.title_suffix
+        && let Some(idx) = html.find("</title>")
+    {
+        html.insert_str(idx, &pages::escape(suffix));
+    }
+
+    if options.favicon {
+        remove_tags(&mut html, r#"<link rel="icon""#);
+        remove_tags(&mut html, r#"<link rel="alternate icon""#);
+
+        let link = format!(
+            r#"<link rel="icon" href="{}{FAVICON_PATH}">"#,
+            prefix.as_deref().unwrap_or_default()
+        );
+
+        if let Some(idx) = html.find("</head>") {
+            html.insert_str(idx, &link);
+        }
+    }
+
+    // Last in the head, so that its rules win over rustdoc's. The reload client finds it by its
+    // attribute to load it again when it changes.
+    if options.custom_css {
+        let link = format!(
+            r#"<link rel="stylesheet" href="{}{CUSTOM_CSS_PATH}" data-custom-css>"#,
+            prefix.as_deref().unwrap_or_default()
+        );
+
+        if let Some(idx) = html.find("</head>") {
+            html.insert_str(idx, &link);
+        }
+    }
+
+    Response::from_parts(parts, Body::from(html))
+}
+
+/// Holds requests for HTML pages while a build is in progress, so that half-written pages aren't
+/// served.
+///
+/// Requests are served anyway once the timeout passes. Other files aren't held, since pages being
+/// loaded may need them.
+///
+/// Arguments:
+/// * `building` - Set while a build is in progress.
+/// * `timeout` - How long to hold requests for at most.
+/// * `request` - The incoming request.
+/// * `next` - The rest of the middleware stack.
+///
+/// Returns:
+/// [`Response`] -> The response to the request.
+pub async fn wait_for_build(
+    State((building, timeout)): State<(watch::Receiver<bool>, Duration)>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if is_page(request.uri().path()) && *building.borrow() {
+        let mut building = building.clone();
+        time::timeout(timeout, building.wait_for(|building| !building))
+            .await
+            .ok();
+    }
+
+    next.run(request).await
+}
+
+/// Records that a browser connected to the reload WebSocket, like a tab left open by an earlier run
+/// of the server reconnecting to it.
+///
+/// Arguments:
+/// * `reconnected` - Set once a browser connects, if it's being tracked.
+/// * `request` - The incoming request.
+/// * `next` - The rest of the middleware stack.
+///
+/// Returns:
+/// [`Response`] -> The response to the request.
+pub async fn track_reconnects(
+    State(reconnected): State<Option<watch::Sender<bool>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    // Variants and stripped prefixes serve the WebSocket under their path.
+    if let Some(reconnected) = reconnected
+        && request.uri().path().ends_with(reload::PATH)
+    {
+        reconnected.send_replace(true);
+    }
+
+    next.run(request).await
+}
+
+/// Sets the `Content-Type` of fonts and WebAssembly files to their registered types, which some
+/// proxies rely on to handle them properly.
+///
+/// Arguments:
+/// * `request` - The incoming request.
+/// * `next` - The rest of the middleware stack.
+///
+/// Returns:
+/// [`Response`] -> The response, with its content type corrected if it's one of [`ASSET_TYPES`].
+pub async fn asset_types(request: Request, next: Next) -> Response {
+    let content_type = ASSET_TYPES
+        .iter()
+        .find(|(extension, _)| request.uri().path().ends_with(extension))
+        .map(|(_, content_type)| *content_type);
+
+    let mut response = next.run(request).await;
+
+    if let Some(content_type) = content_type
+        && response.status().is_success()
+    {
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    }
+
+    response
+}
+
+/// Sets how long browsers cache the files served other than pages, and answers conditional
+/// requests for them.
+///
+/// rustdoc names the files in `static.files/` after a hash of their contents, so those are cached
+/// for good. Others, like the search index, are revalidated on every use, which the `ETag` made of
+/// their size and modification time lets browsers do without downloading them again.
+///
+/// Arguments:
+/// * `request` - The incoming request.
+/// * `next` - The rest of the middleware stack.
+///
+/// Returns:
+/// [`Response`] -> The response, or `304 Not Modified` if the browser's copy is up to date.
+pub async fn cache_files(mut request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+
+    if !(request.method() == Method::GET || request.method() == Method::HEAD) || is_page(path) {
+        return next.run(request).await;
+    }
+
+    let cache_control = if path.starts_with(HASHED_FILES_PATH) {
+        "public, max-age=31536000, immutable"
+    } else {
+        "no-cache"
+    };
+
+    // `If-None-Match` takes precedence over `If-Modified-Since`, which would otherwise be answered
+    // without the `ETag`.
+    let if_none_match = request.headers_mut().remove(IF_NONE_MATCH);
+
+    if if_none_match.is_some() {
+        request.headers_mut().remove(IF_MODIFIED_SINCE);
+    }
+
+    let mut response = next.run(request).await;
+
+    if !(response.status() == StatusCode::OK || response.status() == StatusCode::NOT_MODIFIED) {
+        return response;
+    }
+
+    response
+        .headers_mut()
+        .insert(CACHE_CONTROL, HeaderValue::from_static(cache_control));
+
+    // Files answered with `304 Not Modified` already don't have the size to make the `ETag` of.
+    let Some(etag) = (response.status() == StatusCode::OK)
+        .then(|| etag(&response))
+        .flatten()
+    else {
+        return response;
+    };
+
+    let matches = if_none_match
+        .as_ref()
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| {
+            v.split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || tag.trim_start_matches("W/") == &etag[2..])
+        });
+
+    if matches {
+        let mut not_modified = StatusCode::NOT_MODIFIED.into_response();
+
+        for header in [CACHE_CONTROL, LAST_MODIFIED] {
+            if let Some(value) = response.headers().get(&header) {
+                not_modified.headers_mut().insert(header, value.clone());
+            }
+        }
+
+        response = not_modified;
+    }
+
+    if let Ok(etag) = HeaderValue::from_str(&etag) {
+        response.headers_mut().insert(ETAG, etag);
+    }
+
+    response
+}
+
+/// Logs every request with the address it came from, its response's status, and how long it took.
+///
+/// Nothing is logged if access logging wasn't enabled. Only the method and URI of requests are
+/// logged, never their headers, so that credentials like `Authorization` and `Cookie` don't end up
+/// in the logs. URIs longer than `max_uri_length` are truncated.
+///
+/// Arguments:
+/// * `max_uri_length` - The most characters of a URI to log, if requests are logged at all.
+/// * `request` - The incoming request.
+/// * `next` - The rest of the middleware stack.
+///
+/// Returns:
+/// [`Response`] -> The response to the request.
+pub async fn access_log(
+    State(max_uri_length): State<Option<usize>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(max_uri_length) = max_uri_length else {
+        return next.run(request).await;
+    };
+
+    let start = Instant::now();
+
+    let client = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let method = request.method().clone();
+
+    let uri = request.uri().to_string();
+    let uri = match uri.char_indices().nth(max_uri_length) {
+        Some((idx, _)) => format!("{}...", &uri[..idx]),
+        None => uri,
+    };
+
+    let response = next.run(request).await;
+
+    log::info!(
+        "{client} \"{method} {uri}\" {} in {}ms",
+        response.status().as_u16(),
+        start.elapsed().as_millis()
+    );
+
+    response
+}
+
+/// The `ETag` of a file, made of its size and modification time.
+///
+/// It's weak, since compressed responses don't have the same bytes.
+///
+/// Arguments:
+/// * `response` - The response serving the file.
+///
+/// Returns:
+/// [`Option<String>`] -> The `ETag`, or [`None`] if the response doesn't say when the file was
+/// modified.
+fn etag(response: &Response) -> Option<String> {
+    let headers = response.headers();
+    let mut hasher = DefaultHasher::new();
+
+    headers.get(LAST_MODIFIED)?.as_bytes().hash(&mut hasher);
+    headers
+        .get(CONTENT_LENGTH)
+        .map(HeaderValue::as_bytes)
+        .hash(&mut hasher);
+
+    Some(format!("W/\"{:016x}\"", hasher.finish()))
+}
+
+/// Whether a path likely points to an HTML page, rather than to a script, stylesheet, or other file.
+///
+/// Arguments:
+/// * `path` - The path of the request.
+///
+/// Returns:
+/// [`bool`] -> Whether the path points to a page.
+fn is_page(path: &str) -> bool {
+    path.ends_with('/') || path.ends_with(".html")
+}
+
+/// Removes every tag starting with some text from HTML.
+///
+/// Arguments:
+/// * `html` - The HTML to remove the tags from.
+/// * `start` - The start of the tags, like `<link rel="icon"`.
+fn remove_tags(html: &mut String, start: &str) {
+    while let Some(idx) = html.find(start) {
+        let end = html[idx..]
+            .find('>')
+            .map_or(html.len(), |end| idx + end + 1);
+        html.replace_range(idx..end, "");
+    }
+}
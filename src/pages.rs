@@ -0,0 +1,400 @@
+use std::collections::BTreeMap;
+
+use cargo_metadata::Package;
+
+use crate::ansi;
+use crate::build::{BuildFailure, BuildState, MemberBuild};
+use crate::guide::GuidePage;
+use crate::{doc_dir, is_documented};
+
+const STYLE: &str = r#"<style>
+body { margin: 0; padding: 2rem; font-family: "Source Serif 4", "NanumBarunGothic", serif; }
+h1, h2 { font-family: "Fira Sans", Arial, sans-serif; }
+h1 { font-size: 1.5rem; }
+h2 { font-size: 1.25rem; }
+pre { padding: 1rem; overflow-x: auto; background: #f5f5f5; border-radius: 6px; }
+li { margin: 0.25rem 0; }
+.kind { color: #6e6e6e; }
+</style>"#;
+
+/// Styles for the build error page, following the system's light or dark preference, with colors
+/// for the diagnostics like those of terminals.
+const ERROR_STYLE: &str = r#"<style>
+:root { color-scheme: light dark; }
+body { background: #fff; color: #000; }
+pre { background: #f5f5f5; color: #000; }
+.ansi-bold { font-weight: bold; }
+.ansi-underline { text-decoration: underline; }
+.ansi-0, .ansi-8 { color: #6e6e6e; }
+.ansi-1, .ansi-9 { color: #c41a16; }
+.ansi-2, .ansi-10 { color: #107c10; }
+.ansi-3, .ansi-11 { color: #946200; }
+.ansi-4, .ansi-12 { color: #0b57d0; }
+.ansi-5, .ansi-13 { color: #a626a4; }
+.ansi-6, .ansi-14 { color: #0e7490; }
+.ansi-7, .ansi-15 { color: inherit; }
+@media (prefers-color-scheme: dark) {
+    body { background: #0f1419; color: #e6e6e6; }
+    pre { background: #191f26; color: #e6e6e6; }
+    .ansi-0, .ansi-8 { color: #8c8c8c; }
+    .ansi-1, .ansi-9 { color: #ff6b6b; }
+    .ansi-2, .ansi-10 { color: #7ee787; }
+    .ansi-3, .ansi-11 { color: #f2cc60; }
+    .ansi-4, .ansi-12 { color: #79b8ff; }
+    .ansi-5, .ansi-13 { color: #e29cf5; }
+    .ansi-6, .ansi-14 { color: #67e8f9; }
+}
+</style>"#;
+
+/// Styles for rendered Markdown, roughly matching rustdoc's docblocks.
+const GUIDE_STYLE: &str = r#"<style>
+body { max-width: 960px; margin: 0 auto; line-height: 1.5; color: #000; }
+h1, h2, h3, h4, h5, h6 { font-weight: 500; }
+h2, h3 { border-bottom: 1px solid #ddd; padding-bottom: 0.2rem; }
+a { color: #3873ad; text-decoration: none; }
+a:hover { text-decoration: underline; }
+code { font-family: "Source Code Pro", monospace; font-size: 0.875em; background: #f5f5f5; padding: 0.1em 0.3em; border-radius: 3px; }
+pre code { padding: 0; background: none; }
+blockquote { margin: 0; padding: 0 1rem; color: #6e6e6e; border-left: 4px solid #ddd; }
+table { border-collapse: collapse; }
+th, td { padding: 0.25rem 0.75rem; border: 1px solid #ddd; }
+nav { font-family: "Fira Sans", Arial, sans-serif; }
+</style>"#;
+
+/// Renders the page shown in place of the documentation while the last build is failing.
+///
+/// Arguments:
+/// * `failure` - Why the last build failed.
+///
+/// Returns:
+/// [`String`] -> The page's HTML.
+pub fn build_error(failure: &BuildFailure) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>Build failed</title>
+{STYLE}
+{ERROR_STYLE}
+</head>
+<body>
+<h1><code>{}</code> failed</h1>
+<p>The documentation will be served again once a build succeeds.</p>
+{}<pre>{}</pre>
+</body>
+</html>
+"#,
+        escape(&failure.command),
+        failure
+            .hint
+            .as_deref()
+            .map(|hint| format!("<p><strong>Hint:</strong> {}</p>\n", escape(hint)))
+            .unwrap_or_default(),
+        failure
+            .ansi_output
+            .as_deref()
+            .map(ansi::to_html)
+            .unwrap_or_else(|| escape(&failure.output))
+    )
+}
+
+/// Renders the page shown at `/` until the first build succeeds.
+///
+/// It's reloaded through the reload client once the build finishes, like any other page.
+///
+/// Returns:
+/// [`String`] -> The page's HTML.
+pub fn building() -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>Building documentation...</title>
+{STYLE}
+</head>
+<body>
+<h1>Building documentation...</h1>
+<p>This page will reload once the documentation is built. Its progress is logged by the server.</p>
+</body>
+</html>
+"#
+    )
+}
+
+/// Renders an index linking the documentation of every target of the packages.
+///
+/// Workspace members whose last build failed are flagged instead, without links to documentation
+/// that may not be there.
+///
+/// Arguments:
+/// * `packages` - The documented packages.
+/// * `members` - The outcome of the last build of every workspace member, if they're documented
+///   on their own.
+///
+/// Returns:
+/// [`String`] -> The page's HTML.
+pub fn target_index(packages: &[Package], members: &[MemberBuild]) -> String {
+    let mut sections = String::new();
+
+    for package in packages {
+        let failed = members
+            .iter()
+            .any(|member| member.name == *package.name && member.failure.is_some());
+
+        if failed {
+            sections.push_str(&format!(
+                "<h2>{} <span class=\"kind\">failed</span></h2>\n",
+                escape(&package.name)
+            ));
+            continue;
+        }
+
+        sections.push_str(&format!("<h2>{}</h2>\n<ul>\n", escape(&package.name)));
+
+        let libs: Vec<_> = package.targets.iter().filter(|t| t.is_lib()).collect();
+
+        for target in package.targets.iter().filter(|t| is_documented(t, true)) {
+            // Binaries named like the library aren't documented, since their output collides.
+            if target.is_bin() && libs.iter().any(|lib| doc_dir(lib) == doc_dir(target)) {
+                continue;
+            }
+
+            let kind = if target.is_lib() {
+                "library"
+            } else if target.is_bin() {
+                "binary"
+            } else {
+                "example"
+            };
+
+            sections.push_str(&format!(
+                "<li><a href=\"{}/index.html\">{}</a> <span class=\"kind\">{kind}</span></li>\n",
+                escape(&doc_dir(target)),
+                escape(&target.name)
+            ));
+        }
+
+        sections.push_str("</ul>\n");
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>Documentation</title>
+{STYLE}
+</head>
+<body>
+<h1>Documentation</h1>
+{sections}</body>
+</html>
+"#
+    )
+}
+
+/// Renders an index linking the documentation of every variant.
+///
+/// Arguments:
+/// * `names` - The names of the variants, which they're served under.
+///
+/// Returns:
+/// [`String`] -> The page's HTML.
+pub fn variant_index(names: &[&str]) -> String {
+    let mut items = String::new();
+
+    for name in names {
+        items.push_str(&format!(
+            "<li><a href=\"{0}/\">{0}</a></li>\n",
+            escape(name)
+        ));
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>Documentation</title>
+{STYLE}
+</head>
+<body>
+<h1>Documentation</h1>
+<ul>
+{items}</ul>
+</body>
+</html>
+"#
+    )
+}
+
+/// Renders the index of the feature sets given with `--feature-set`, with whether each of them
+/// built.
+///
+/// Arguments:
+/// * `sets` - The name of every set, and the outcome of its builds.
+///
+/// Returns:
+/// [`String`] -> The page's HTML.
+pub fn feature_matrix(sets: &[(&str, BuildState)]) -> String {
+    let mut items = String::new();
+
+    for (name, state) in sets {
+        let status = match (&state.failure, state.ready) {
+            (Some(_), _) => "failed",
+            (None, true) => "built",
+            (None, false) => "building",
+        };
+
+        items.push_str(&format!(
+            "<li><a href=\"{0}/\">{0}</a> <span class=\"kind\">{status}</span></li>\n",
+            escape(name)
+        ));
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>Feature sets</title>
+{STYLE}
+</head>
+<body>
+<h1>Feature sets</h1>
+<ul>
+{items}</ul>
+</body>
+</html>
+"#
+    )
+}
+
+/// Renders a page of the guide.
+///
+/// Arguments:
+/// * `page` - The rendered Markdown page.
+/// * `depth` - How many directories deep the page is in the guide, to link back to its index.
+///
+/// Returns:
+/// [`String`] -> The page's HTML.
+pub fn guide_page(page: &GuidePage, depth: usize) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>{}</title>
+{STYLE}
+{GUIDE_STYLE}
+</head>
+<body>
+<nav><a href="{}">Guide</a></nav>
+{}</body>
+</html>
+"#,
+        escape(&page.title),
+        if depth == 0 {
+            "./".to_string()
+        } else {
+            "../".repeat(depth)
+        },
+        page.content
+    )
+}
+
+/// Renders an index linking every page of the guide.
+///
+/// Arguments:
+/// * `pages` - The guide's pages, by their path relative to the guide.
+///
+/// Returns:
+/// [`String`] -> The page's HTML.
+pub fn guide_index(pages: &BTreeMap<String, GuidePage>) -> String {
+    let mut items = String::new();
+
+    for (path, page) in pages {
+        items.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            escape(path),
+            escape(&page.title)
+        ));
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>Guide</title>
+{STYLE}
+{GUIDE_STYLE}
+</head>
+<body>
+<h1>Guide</h1>
+<ul>
+{items}</ul>
+</body>
+</html>
+"#
+    )
+}
+
+/// Renders a sitemap listing every page of the documentation.
+///
+/// Arguments:
+/// * `base` - The URL the documentation is reachable at from outside, without a trailing slash.
+/// * `paths` - The paths of the pages, relative to the documentation's root.
+///
+/// Returns:
+/// [`String`] -> The sitemap's XML.
+pub fn sitemap(base: &str, paths: &[String]) -> String {
+    let mut urls = String::new();
+
+    for path in paths {
+        urls.push_str(&format!(
+            "<url><loc>{}</loc></url>\n",
+            escape(&format!("{base}/{path}"))
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+{urls}</urlset>
+"#
+    )
+}
+
+/// Escapes text for it to be safely embedded in HTML.
+///
+/// Arguments:
+/// * `text` - The text to escape.
+///
+/// Returns:
+/// [`String`] -> The escaped text.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,172 @@
+// Reloads the page whenever the documentation is rebuilt.
+(() => {
+    const path = document.currentScript.dataset.path;
+    let rendered = Number(document.currentScript.dataset.generation);
+    const preserveScroll = document.currentScript.dataset.preserveScroll === "true";
+    const smartReload = document.currentScript.dataset.smartReload === "true";
+    const protocol = location.protocol === "https:" ? "wss:" : "ws:";
+    const scrollKey = "docs-server-scroll";
+
+    // The element the page's fragment points at, like the method a link to it was for.
+    function anchor() {
+        const id = decodeURIComponent(location.hash.slice(1));
+        return id ? document.getElementById(id) : null;
+    }
+
+    // Where the element the fragment points at is on the screen, if it's there.
+    function anchorTop() {
+        const element = anchor();
+        return element ? element.getBoundingClientRect().top : null;
+    }
+
+    // Scrolls the element the fragment points at back to where it was on the screen, or into view
+    // if that isn't known, keeping it in place even if what's above it changed. Elements that
+    // don't exist yet are waited for for a while.
+    function restoreAnchor(top) {
+        const restore = (element) => {
+            if (top === null) {
+                element.scrollIntoView();
+            } else {
+                scrollBy(0, element.getBoundingClientRect().top - top);
+            }
+        };
+
+        const element = anchor();
+
+        if (element) {
+            restore(element);
+            return;
+        }
+
+        const observer = new MutationObserver(() => {
+            const element = anchor();
+
+            if (element) {
+                observer.disconnect();
+                restore(element);
+            }
+        });
+
+        observer.observe(document.documentElement, { childList: true, subtree: true });
+        setTimeout(() => observer.disconnect(), 5000);
+    }
+
+    // Restores where the page was scrolled to before it was reloaded, once it's laid out.
+    const saved = JSON.parse(sessionStorage.getItem(scrollKey));
+    sessionStorage.removeItem(scrollKey);
+
+    if (saved && saved.path === location.pathname && saved.hash === location.hash) {
+        addEventListener("load", () => {
+            if (preserveScroll) {
+                scrollTo(saved.x, saved.y);
+
+                if (saved.anchorTop !== null) {
+                    restoreAnchor(saved.anchorTop);
+                }
+            } else if (location.hash) {
+                restoreAnchor(null);
+            }
+        });
+    }
+
+    function reload() {
+        sessionStorage.setItem(scrollKey, JSON.stringify({
+            path: location.pathname,
+            hash: location.hash,
+            x: scrollX,
+            y: scrollY,
+            anchorTop: anchorTop(),
+        }));
+
+        location.reload();
+    }
+
+    // Swaps the content and sidebar of the page for those of its latest version, failing if the
+    // page, or the one it's swapped with, isn't a page of the documentation, like an error page.
+    async function swap(generation) {
+        const response = await fetch(location.href, { cache: "no-store" });
+
+        if (!response.ok) {
+            throw new Error(`The page answered ${response.status}`);
+        }
+
+        const latest = new DOMParser().parseFromString(await response.text(), "text/html");
+        const main = document.querySelector("main");
+        const latestMain = latest.querySelector("main");
+
+        if (!main || !latestMain) {
+            throw new Error("There's no content to swap");
+        }
+
+        const x = scrollX;
+        const y = scrollY;
+        const top = anchorTop();
+
+        main.replaceWith(document.adoptNode(latestMain));
+
+        const sidebar = document.querySelector(".sidebar");
+        const latestSidebar = latest.querySelector(".sidebar");
+
+        if (sidebar && latestSidebar) {
+            sidebar.replaceWith(document.adoptNode(latestSidebar));
+        }
+
+        document.title = latest.title;
+        rendered = generation;
+
+        if (preserveScroll) {
+            scrollTo(x, y);
+
+            if (top !== null) {
+                restoreAnchor(top);
+            }
+        } else if (location.hash) {
+            restoreAnchor(null);
+        }
+    }
+
+    // Loads the custom stylesheet again, bypassing the browser's cache.
+    function restyle() {
+        for (const link of document.querySelectorAll("link[data-custom-css]")) {
+            const url = new URL(link.href);
+            url.searchParams.set("v", Date.now());
+            link.href = url.href;
+        }
+    }
+
+    function connect() {
+        const socket = new WebSocket(`${protocol}//${location.host}${path}`);
+
+        socket.addEventListener("message", (event) => {
+            const message = JSON.parse(event.data);
+
+            // Only the custom stylesheet changed, so it's loaded again without touching the page.
+            if (message.type === "css") {
+                restyle();
+                return;
+            }
+
+            // The page is outdated if it was served before the last build, which can also have
+            // happened while disconnected or before the server restarted.
+            if (message.generation === rendered) {
+                return;
+            }
+
+            // Pages to navigate to are relative to the documentation's root, which the WebSocket
+            // is served under.
+            const page = message.page && `${path.slice(0, path.lastIndexOf("/"))}/${message.page}`;
+
+            if (page && page !== location.pathname) {
+                location.href = page;
+            } else if (smartReload && message.structural === false) {
+                swap(message.generation).catch(reload);
+            } else {
+                reload();
+            }
+        });
+
+        socket.addEventListener("close", () => setTimeout(connect, 1000));
+    }
+
+    connect();
+})();
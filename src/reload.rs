@@ -0,0 +1,245 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::extract::State;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::response::Response;
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{Notify, broadcast};
+use tokio::time::{self, Interval};
+
+/// The script injected into served pages to reload them when the documentation is rebuilt.
+///
+/// It reads the path of the reload WebSocket from its `data-path` attribute, the build
+/// generation the page was served at from its `data-generation` one, whether to keep the
+/// scroll position across reloads from its `data-preserve-scroll` one, and whether to swap the
+/// content of pages in place instead of reloading them from its `data-smart-reload` one.
+pub const CLIENT: &str = include_str!("reload.js");
+
+/// The path the reload WebSocket is served at.
+pub const PATH: &str = "/__reload";
+
+/// Notifies connected browsers when the documentation is rebuilt.
+///
+/// Every build bumps a generation number, which browsers compare against the one the page they
+/// show was served at to catch up on builds that happened while they were disconnected. It starts
+/// at the time the server started, so that it also differs after a restart.
+///
+/// Builds finishing in quick succession are coalesced into a single reload, so that browsers don't
+/// flicker through each of them.
+///
+/// A reload can also point browsers at a page to navigate to instead, relative to the root of the
+/// documentation. Reloads after builds that didn't add or remove pages are told apart, so that
+/// browsers can swap the content of the page in place with `--smart-reload`.
+///
+/// Browsers can also be told to only load the `--custom-css` stylesheet again, which doesn't bump
+/// the generation since the documentation didn't change.
+#[derive(Clone)]
+pub struct Reloader {
+    sender: broadcast::Sender<(u64, Option<String>, bool)>,
+    /// Tells browsers to load the custom stylesheet again.
+    styles: broadcast::Sender<()>,
+    generation: Arc<AtomicU64>,
+    /// The page to navigate to on the next reload, if any, and whether any of the builds it's for
+    /// was structural.
+    next: Arc<Mutex<(Option<String>, bool)>>,
+    /// Notified on every build when reloads are debounced.
+    pending: Option<Arc<Notify>>,
+}
+
+impl Reloader {
+    /// Creates a reloader, spawning the task that debounces reloads if there's a debounce.
+    ///
+    /// Arguments:
+    /// * `debounce` - How long to wait for more builds before telling browsers to reload.
+    ///
+    /// Returns:
+    /// [`Reloader`] -> The reloader.
+    pub fn new(debounce: Duration) -> Self {
+        let (sender, _) = broadcast::channel(16);
+
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        let generation = Arc::new(AtomicU64::new(started));
+        let next: Arc<Mutex<(Option<String>, bool)>> = Default::default();
+
+        let pending = (!debounce.is_zero()).then(|| {
+            let pending = Arc::new(Notify::new());
+
+            let sender = sender.clone();
+            let generation = generation.clone();
+            let next = next.clone();
+            let notified = pending.clone();
+
+            tokio::spawn(async move {
+                loop {
+                    notified.notified().await;
+
+                    // Every build finishing before the debounce passes without any others is part
+                    // of the same reload.
+                    while time::timeout(debounce, notified.notified()).await.is_ok() {}
+
+                    let (page, structural) = std::mem::take(&mut *next.lock().unwrap());
+                    let generation = generation.load(Ordering::Relaxed);
+                    sender.send((generation, page, structural)).ok();
+                }
+            });
+
+            pending
+        });
+
+        Self {
+            sender,
+            styles: broadcast::channel(16).0,
+            generation,
+            next,
+            pending,
+        }
+    }
+
+    /// Bumps the build generation and tells every connected browser to reload, once the debounce
+    /// passes without other builds.
+    ///
+    /// Pages served from now on already carry the new generation.
+    pub fn reload(&self) {
+        self.reload_to(None, true);
+    }
+
+    /// Like [`Reloader::reload()`], but tells browsers to navigate to a page instead of reloading
+    /// the one they show.
+    ///
+    /// When builds are coalesced into a single reload, the page of the last one is navigated to,
+    /// and the reload is structural if any of them was.
+    ///
+    /// Arguments:
+    /// * `page` - The page to navigate to, relative to the root of the documentation, if any.
+    /// * `structural` - Whether the build added or removed pages, or failed, so that pages have
+    ///   to be reloaded in full.
+    pub fn reload_to(&self, page: Option<String>, structural: bool) {
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+
+        match &self.pending {
+            Some(pending) => {
+                let mut next = self.next.lock().unwrap();
+                *next = (page, next.1 || structural);
+                drop(next);
+
+                pending.notify_one();
+            }
+            None => {
+                self.sender.send((generation, page, structural)).ok();
+            }
+        }
+    }
+
+    /// Tells every connected browser to load the custom stylesheet again, without reloading the
+    /// page.
+    pub fn restyle(&self) {
+        self.styles.send(()).ok();
+    }
+
+    /// The generation of the last build.
+    ///
+    /// Returns:
+    /// [`u64`] -> The build generation.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
+    }
+}
+
+/// Upgrades a request to the reload WebSocket.
+///
+/// Arguments:
+/// * `reloader` - The reloader to subscribe the browser to.
+/// * `ping` - How often to ping the browser to keep the connection alive, if at all.
+/// * `upgrade` - The WebSocket upgrade request.
+///
+/// Returns:
+/// [`Response`] -> The upgrade response.
+pub async fn websocket(
+    State((reloader, ping)): State<(Reloader, Option<Duration>)>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    upgrade.on_upgrade(move |socket| handle(socket, reloader, ping))
+}
+
+/// Sends reload messages to a connected browser until it disconnects.
+///
+/// The browser is first sent the current build generation, and then every new one as builds
+/// finish, along with the page to navigate to if there's one and whether the build was
+/// structural. Changes to the custom stylesheet are sent as `css` messages. Proxies tend to drop
+/// idle connections, so pings are sent in between.
+async fn handle(mut socket: WebSocket, reloader: Reloader, ping: Option<Duration>) {
+    let mut builds = reloader.sender.subscribe();
+    let mut styles = reloader.styles.subscribe();
+    let mut pings = ping.map(|ping| time::interval_at(time::Instant::now() + ping, ping));
+
+    let hello = json!({ "type": "hello", "generation": reloader.generation() });
+
+    if socket
+        .send(Message::Text(hello.to_string().into()))
+        .await
+        .is_err()
+    {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            reload = builds.recv() => {
+                let (generation, page, structural) = match reload {
+                    Ok(reload) => reload,
+                    Err(RecvError::Lagged(_)) => (reloader.generation(), None, true),
+                    Err(RecvError::Closed) => return,
+                };
+
+                let reload = json!({
+                    "type": "reload",
+                    "generation": generation,
+                    "page": page,
+                    "structural": structural,
+                });
+
+                if socket.send(Message::Text(reload.to_string().into())).await.is_err() {
+                    return;
+                }
+            }
+            restyle = styles.recv() => {
+                if let Err(RecvError::Closed) = restyle {
+                    return;
+                }
+
+                let restyle = json!({ "type": "css" });
+
+                if socket.send(Message::Text(restyle.to_string().into())).await.is_err() {
+                    return;
+                }
+            }
+            _ = tick(&mut pings) => {
+                if socket.send(Message::Ping(Default::default())).await.is_err() {
+                    return;
+                }
+            }
+            message = socket.recv() => {
+                if let None | Some(Err(_)) | Some(Ok(Message::Close(_))) = message {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Waits for the next tick of an interval, or forever if there's none.
+async fn tick(interval: &mut Option<Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
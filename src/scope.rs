@@ -0,0 +1,91 @@
+use std::path::{Path, PathBuf};
+
+/// Narrows the rebuilds triggered by changes to the targets of a package whose sources changed,
+/// like only documenting a binary when a file of its own changed.
+///
+/// A file belongs to the target whose root source file it is, or otherwise to the one whose root
+/// source file's directory is the deepest one containing it. Files that more than one target
+/// could own, like modules next to both `src/lib.rs` and `src/main.rs`, or that no target owns,
+/// like `Cargo.toml`, need the whole package to be rebuilt.
+#[derive(Clone)]
+pub struct TargetScope {
+    /// The crate's root, which the changed paths are relative to.
+    root: PathBuf,
+    /// The root source file of every target of the package, with the arguments that document it
+    /// on its own, or [`None`] if it can't be.
+    targets: Vec<(PathBuf, Option<Vec<String>>)>,
+}
+
+impl TargetScope {
+    /// Creates a scope for the targets of a package.
+    ///
+    /// Arguments:
+    /// * `root` - The crate's root, which the changed paths are relative to.
+    /// * `targets` - The root source file of every target of the package, with the arguments
+    ///   that document it on its own, like `--bin <NAME>`, or [`None`] if it can't be.
+    ///
+    /// Returns:
+    /// [`TargetScope`] -> The scope.
+    pub fn new(root: PathBuf, targets: Vec<(PathBuf, Option<Vec<String>>)>) -> Self {
+        let targets = targets
+            .into_iter()
+            .map(|(src, selection)| (src.canonicalize().unwrap_or(src), selection))
+            .collect();
+
+        Self { root, targets }
+    }
+
+    /// Finds the arguments that only document the targets some changed files belong to.
+    ///
+    /// Arguments:
+    /// * `changes` - The changed files, relative to the crate's root.
+    ///
+    /// Returns:
+    /// [`Option<Vec<String>>`] -> The arguments, or [`None`] if the whole package has to be
+    /// rebuilt.
+    pub fn selection(&self, changes: &[PathBuf]) -> Option<Vec<String>> {
+        let mut selection: Vec<&[String]> = vec![];
+
+        for path in changes {
+            let target = self.owner(&self.root.join(path))?;
+
+            if !selection.contains(&target) {
+                selection.push(target);
+            }
+        }
+
+        Some(selection.concat())
+    }
+
+    /// Finds the arguments that document the target a file belongs to.
+    ///
+    /// Arguments:
+    /// * `path` - The file, joined to the crate's root.
+    ///
+    /// Returns:
+    /// [`Option<&[String]>`] -> The arguments, or [`None`] if the file belongs to no target that
+    /// can be documented on its own, or there's more than one it could belong to.
+    fn owner(&self, path: &Path) -> Option<&[String]> {
+        if let Some((_, selection)) = self.targets.iter().find(|(src, _)| src == path) {
+            return selection.as_deref();
+        }
+
+        let containing: Vec<_> = self
+            .targets
+            .iter()
+            .filter_map(|(src, selection)| {
+                let dir = src.parent()?;
+                path.starts_with(dir)
+                    .then(|| (dir.components().count(), selection))
+            })
+            .collect();
+
+        let deepest = containing.iter().map(|(depth, _)| *depth).max()?;
+        let mut owners = containing.iter().filter(|(depth, _)| *depth == deepest);
+
+        match (owners.next(), owners.next()) {
+            (Some((_, selection)), None) => selection.as_deref(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::env;
+use std::ffi::OsString;
+use std::net::SocketAddr;
+use std::path::Path;
+
+use crate::pages::escape;
+use crate::{Args, DocsServerError, ServiceManager, listen_address};
+
+/// The name of the service, and of the files it's installed as.
+const NAME: &str = "docs-server";
+
+/// Writes a service file running the server as it was just started, by the same binary, from the
+/// current directory, and with the same options and environment variables.
+///
+/// Arguments:
+/// * `args` - The options of the server, for the addresses the socket unit listens at.
+/// * `manager` - The service manager to write the file for.
+/// * `socket_activation` - Whether to also write a socket unit starting the server, with systemd.
+/// * `flags` - The arguments the server was started with, without the binary and the subcommand.
+///
+/// Returns:
+/// [`Result<String, DocsServerError>`] -> The service file, or why it couldn't be written.
+pub fn generate(
+    args: &Args,
+    manager: ServiceManager,
+    socket_activation: bool,
+    flags: &[OsString],
+) -> Result<String, DocsServerError> {
+    if socket_activation && manager != ServiceManager::Systemd {
+        return Err(DocsServerError::Usage(
+            "Only systemd can start the server on the first connection with \
+             `--socket-activation`!"
+                .to_string(),
+        ));
+    }
+
+    let binary = env::current_exe()?;
+    let dir = env::current_dir()?;
+
+    let mut command = vec![binary.to_string_lossy().into_owned()];
+    command.extend(flags.iter().map(|flag| flag.to_string_lossy().into_owned()));
+
+    // cargo is looked up in the `PATH`, which service managers set to a minimal one. Variables
+    // that aren't valid UTF-8 can't be written to the file, so they're left out.
+    let envs: Vec<_> = env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        .filter(|(name, _)| name == "PATH" || name.starts_with("DOCS_SERVER_"))
+        .collect();
+
+    Ok(match manager {
+        ServiceManager::Systemd if socket_activation => format!(
+            "{}\n{}",
+            systemd_socket(&addresses(args), args.interface.as_deref()),
+            systemd_service(&command, &dir, &envs, true)
+        ),
+        ServiceManager::Systemd => systemd_service(&command, &dir, &envs, false),
+        ServiceManager::Launchd => launchd_agent(&command, &dir, &envs),
+    })
+}
+
+/// The addresses the server binds to, like when it runs.
+///
+/// Arguments:
+/// * `args` - The options of the server.
+///
+/// Returns:
+/// [`Vec<SocketAddr>`] -> The addresses of `--bind`, on the loopback address with `--localhost`.
+fn addresses(args: &Args) -> Vec<SocketAddr> {
+    let mut addresses = vec![];
+
+    for address in &args.bind {
+        let address = listen_address(args, *address);
+
+        if !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
+
+    addresses
+}
+
+/// Writes a systemd service unit.
+///
+/// Arguments:
+/// * `command` - The binary and the arguments to run it with.
+/// * `dir` - The directory to run it from.
+/// * `envs` - The environment variables to run it with.
+/// * `socket` - Whether it's started by the socket unit of [`systemd_socket`].
+///
+/// Returns:
+/// [`String`] -> The unit.
+fn systemd_service(
+    command: &[String],
+    dir: &Path,
+    envs: &[(String, String)],
+    socket: bool,
+) -> String {
+    let exec = command
+        .iter()
+        .map(|arg| systemd_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let environment: String = envs
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                "Environment={}\n",
+                systemd_quote(&format!("{name}={value}"))
+            )
+        })
+        .collect();
+
+    format!(
+        r#"# {NAME}.service, generated by `docs generate-service`.
+#
+# Install it at `~/.config/systemd/user/{NAME}.service`, and start it with
+# `systemctl --user enable --now {NAME}.{}`.
+[Unit]
+Description=Documentation server for {}
+After=network.target
+{}
+[Service]
+# Relative paths in the options, like the crate's root, are resolved from here.
+WorkingDirectory={}
+ExecStart={exec}
+{environment}# Build failures don't stop the server, so it only exits if it couldn't start.
+Restart=on-failure
+
+[Install]
+WantedBy=default.target
+"#,
+        if socket { "socket" } else { "service" },
+        dir.display(),
+        if socket {
+            format!("Requires={NAME}.socket\n")
+        } else {
+            String::new()
+        },
+        // Paths aren't quoted, only their specifiers escaped.
+        dir.to_string_lossy().replace('%', "%%"),
+    )
+}
+
+/// Writes a systemd socket unit, whose sockets the server serves at instead of binding its own.
+///
+/// Arguments:
+/// * `addresses` - The addresses to listen at.
+/// * `interface` - The network interface to only listen on, with `--interface`.
+///
+/// Returns:
+/// [`String`] -> The unit.
+fn systemd_socket(addresses: &[SocketAddr], interface: Option<&str>) -> String {
+    let mut listen: String = addresses
+        .iter()
+        .map(|address| format!("ListenStream={address}\n"))
+        .collect();
+
+    // Bound to the interface rather than to its address, which can change while the socket is
+    // open.
+    if let Some(interface) = interface {
+        listen.push_str(&format!("BindToDevice={interface}\n"));
+    }
+
+    format!(
+        r#"# {NAME}.socket, generated by `docs generate-service`.
+#
+# Install it at `~/.config/systemd/user/{NAME}.socket`, next to `{NAME}.service` below.
+# The server is started on the first connection, and the sockets stay open across restarts.
+[Unit]
+Description=Documentation server socket
+
+[Socket]
+{listen}
+[Install]
+WantedBy=sockets.target
+"#
+    )
+}
+
+/// Writes a launchd agent's property list.
+///
+/// Arguments:
+/// * `command` - The binary and the arguments to run it with.
+/// * `dir` - The directory to run it from.
+/// * `envs` - The environment variables to run it with.
+///
+/// Returns:
+/// [`String`] -> The property list.
+fn launchd_agent(command: &[String], dir: &Path, envs: &[(String, String)]) -> String {
+    let arguments: String = command
+        .iter()
+        .map(|arg| format!("        <string>{}</string>\n", escape(arg)))
+        .collect();
+
+    let environment: String = envs
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                "        <key>{}</key>\n        <string>{}</string>\n",
+                escape(name),
+                escape(value)
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<!--
+    {NAME}.plist, generated by `docs generate-service`.
+
+    Install it at `~/Library/LaunchAgents/{NAME}.plist`, and start it with
+    `launchctl load ~/Library/LaunchAgents/{NAME}.plist`.
+-->
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{NAME}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <!-- Relative paths in the options, like the crate's root, are resolved from here. -->
+    <key>WorkingDirectory</key>
+    <string>{}</string>
+    <key>EnvironmentVariables</key>
+    <dict>
+{environment}    </dict>
+    <key>RunAtLoad</key>
+    <true/>
+    <!-- Build failures don't stop the server, so it's only restarted if it couldn't start. -->
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+</dict>
+</plist>
+"#,
+        escape(&dir.to_string_lossy()),
+    )
+}
+
+/// Quotes an argument of a systemd unit's command line or environment, if it needs to be.
+///
+/// Arguments:
+/// * `arg` - The argument.
+///
+/// Returns:
+/// [`String`] -> The argument, with systemd's specifiers and variables escaped.
+fn systemd_quote(arg: &str) -> String {
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+
+    if needs_quotes(arg) {
+        format!("\"{escaped}\"")
+    } else {
+        escaped
+    }
+}
+
+/// Quotes an argument like for a POSIX shell, if it needs to be.
+///
+/// Arguments:
+/// * `arg` - The argument.
+///
+/// Returns:
+/// [`String`] -> The argument, in single quotes if it has to be.
+pub(crate) fn shell_quote(arg: &str) -> String {
+    if needs_quotes(arg) {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    } else {
+        arg.to_string()
+    }
+}
+
+/// Whether an argument has to be quoted to be passed as it is, to a shell or in a systemd unit.
+///
+/// Only arguments made of letters, digits, and `_-./:=,+@%` are left unquoted, since every other
+/// character means something to one of them, like `$`, `;`, `*`, or a backtick.
+///
+/// Arguments:
+/// * `arg` - The argument.
+///
+/// Returns:
+/// [`bool`] -> Whether it has to be quoted.
+fn needs_quotes(arg: &str) -> bool {
+    arg.is_empty()
+        || !arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./:=,+@%".contains(c))
+}
@@ -0,0 +1,133 @@
+use std::convert::Infallible;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use axum::extract::{Request, State};
+use axum::response::{IntoResponse, Response};
+use tower::ServiceExt;
+use tower_http::services::ServeDir;
+use walkdir::WalkDir;
+
+/// Copies of the documentation, taken after every successful build, that are served instead of
+/// the directory being built to.
+///
+/// The served copy is swapped for a new one only once it's complete, so that rebuilds never serve
+/// half-written pages. Until the first copy is taken, the documentation is served as is.
+#[derive(Clone)]
+pub struct Snapshots {
+    /// The directory the documentation is generated at.
+    doc_root: PathBuf,
+    /// The directory the copies are taken into, one per subdirectory.
+    dir: PathBuf,
+    /// The directory being served.
+    current: Arc<RwLock<PathBuf>>,
+    /// How many copies were taken, to name the next one.
+    taken: Arc<AtomicU64>,
+}
+
+impl Snapshots {
+    /// Creates the snapshots of a directory, removing the ones left by previous runs.
+    ///
+    /// Arguments:
+    /// * `doc_root` - The directory the documentation is generated at.
+    /// * `dir` - The directory to take the copies into.
+    ///
+    /// Returns:
+    /// [`io::Result<Snapshots>`] -> The snapshots, or why the old ones couldn't be removed.
+    pub async fn new(doc_root: PathBuf, dir: PathBuf) -> io::Result<Self> {
+        match tokio::fs::remove_dir_all(&dir).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        Ok(Self {
+            current: Arc::new(RwLock::new(doc_root.clone())),
+            doc_root,
+            dir,
+            taken: Default::default(),
+        })
+    }
+
+    /// Copies the documentation and starts serving the copy.
+    ///
+    /// The copy served before it is removed once the next one is taken, so that requests still
+    /// reading it can finish.
+    ///
+    /// Returns:
+    /// [`io::Result<()>`] -> Why the copy couldn't be taken, if it couldn't.
+    pub async fn take(&self) -> io::Result<()> {
+        let taken = self.taken.fetch_add(1, Ordering::Relaxed);
+        let snapshot = self.dir.join(taken.to_string());
+
+        let doc_root = self.doc_root.clone();
+        let target = snapshot.clone();
+
+        tokio::task::spawn_blocking(move || copy_dir(&doc_root, &target))
+            .await
+            .expect("Snapshot task failed!")?;
+
+        *self.current.write().unwrap() = snapshot;
+
+        if let Some(stale) = taken.checked_sub(2) {
+            tokio::fs::remove_dir_all(self.dir.join(stale.to_string()))
+                .await
+                .ok();
+        }
+
+        Ok(())
+    }
+
+    /// The directory being served.
+    ///
+    /// Returns:
+    /// [`PathBuf`] -> The latest copy, or the documentation itself if none was taken yet.
+    pub fn current(&self) -> PathBuf {
+        self.current.read().unwrap().clone()
+    }
+}
+
+/// Serves files from the latest snapshot, like the documentation is served without them.
+///
+/// Arguments:
+/// * `snapshots` - The snapshots.
+/// * `request` - The incoming request.
+///
+/// Returns:
+/// [`Response`] -> The file, or the response of [`ServeDir`] if it couldn't be served.
+pub async fn serve(State(snapshots): State<Snapshots>, request: Request) -> Response {
+    ServeDir::new(snapshots.current())
+        .append_index_html_on_directories(true)
+        .oneshot(request)
+        .await
+        .unwrap_or_else(|e: Infallible| match e {})
+        .into_response()
+}
+
+/// Copies a directory recursively.
+///
+/// Files are copied rather than hard-linked, since rustdoc overwrites its output in place and
+/// would change the copies with it.
+///
+/// Arguments:
+/// * `from` - The directory to copy.
+/// * `to` - Where to copy it to. Must not exist.
+///
+/// Returns:
+/// [`io::Result<()>`] -> Why the directory couldn't be copied, if it couldn't.
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    for entry in WalkDir::new(from) {
+        let entry = entry?;
+        let target = to.join(entry.path().strip_prefix(from).unwrap_or(entry.path()));
+
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use tokio::task::JoinHandle;
+use tokio::time;
+
+const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+static SPINNING: AtomicBool = AtomicBool::new(false);
+
+/// Whether a spinner is currently being animated on the terminal.
+///
+/// Returns:
+/// [`bool`] -> Whether a spinner is running.
+pub fn is_spinning() -> bool {
+    SPINNING.load(Ordering::Relaxed)
+}
+
+/// A spinner animated on the standard error while a long task runs.
+///
+/// The spinner is cleared when dropped. It doesn't check whether the standard error is a
+/// terminal, so only start it if it is.
+pub struct Spinner {
+    handle: JoinHandle<()>,
+    done: Arc<AtomicBool>,
+}
+
+impl Spinner {
+    /// Starts animating a spinner next to a message and the time elapsed since it started.
+    ///
+    /// Arguments:
+    /// * `message` - The message to display next to the spinner.
+    ///
+    /// Returns:
+    /// [`Spinner`] -> The running spinner.
+    pub fn start(message: impl Into<String>) -> Self {
+        let message = message.into();
+        let start = Instant::now();
+        let done = Arc::new(AtomicBool::new(false));
+        let finished = done.clone();
+
+        SPINNING.store(true, Ordering::Relaxed);
+
+        let handle = tokio::spawn(async move {
+            let mut interval = time::interval(Duration::from_millis(80));
+
+            for frame in FRAMES.iter().cycle() {
+                interval.tick().await;
+
+                let mut stderr = std::io::stderr().lock();
+
+                // Checked while holding the lock so that no frame is drawn after clearing it.
+                if finished.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                write!(
+                    stderr,
+                    "\r\x1b[2K{frame} {message} {:.1}s",
+                    start.elapsed().as_secs_f32()
+                )
+                .ok();
+                stderr.flush().ok();
+            }
+        });
+
+        Self { handle, done }
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.handle.abort();
+
+        let mut stderr = std::io::stderr().lock();
+        self.done.store(true, Ordering::Relaxed);
+        SPINNING.store(false, Ordering::Relaxed);
+        write!(stderr, "\r\x1b[2K").ok();
+        stderr.flush().ok();
+    }
+}
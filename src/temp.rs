@@ -0,0 +1,82 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// How many temporary target directories were created, to name each of them differently.
+static CREATED: AtomicU32 = AtomicU32::new(0);
+
+/// A temporary target directory, removed once dropped.
+///
+/// Dropping happens when the server stops, whether it's asked to or it panics. It's left behind
+/// if the process is killed without a chance to clean up, like with `SIGKILL`.
+pub struct TempTarget {
+    path: PathBuf,
+}
+
+impl TempTarget {
+    /// Creates an empty target directory in the platform's temporary directory.
+    ///
+    /// Returns:
+    /// [`io::Result<TempTarget>`] -> The directory, or why it couldn't be created.
+    pub fn create() -> io::Result<Self> {
+        loop {
+            let path = std::env::temp_dir().join(format!(
+                "docs-server-{}-{}",
+                std::process::id(),
+                CREATED.fetch_add(1, Ordering::Relaxed)
+            ));
+
+            // Names that are taken, like by an earlier process that had the same ID, are skipped.
+            match std::fs::create_dir(&path) {
+                Ok(()) => return Ok(Self { path }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// The path of the directory.
+    ///
+    /// Returns:
+    /// [`&Path`] -> The path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempTarget {
+    fn drop(&mut self) {
+        match std::fs::remove_dir_all(&self.path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => log::warn!(
+                "Could not remove the temporary target directory `{}`: {e}",
+                self.path.display()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::panic::{self, AssertUnwindSafe};
+
+    use super::TempTarget;
+
+    #[test]
+    fn removed_after_panic() {
+        let mut path = None;
+
+        let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+            let target = TempTarget::create().unwrap();
+            fs::create_dir_all(target.path().join("doc")).unwrap();
+            path = Some(target.path().to_owned());
+
+            panic!("The server panicked!");
+        }));
+
+        assert!(panicked.is_err());
+        assert!(!path.unwrap().exists());
+    }
+}
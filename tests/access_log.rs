@@ -0,0 +1,30 @@
+//! Logging every request with `--access-log`.
+
+mod common;
+
+use common::{Server, wait_until};
+
+#[test]
+fn never_logs_credentials() {
+    let server = Server::start_logged("access-log", &["--access-log"]);
+
+    let page = server.request(
+        "GET",
+        "/fixture/index.html?logged",
+        &[
+            ("Authorization", "Bearer secret-token"),
+            ("Cookie", "session=secret-cookie"),
+        ],
+    );
+    assert_eq!(page.status, 200);
+
+    wait_until("the request is logged", || {
+        server
+            .output()
+            .contains("\"GET /fixture/index.html?logged\" 200")
+    });
+
+    let output = server.output();
+    assert!(!output.contains("secret-token"));
+    assert!(!output.contains("secret-cookie"));
+}
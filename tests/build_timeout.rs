@@ -0,0 +1,14 @@
+//! Killing `cargo doc` once it runs for longer than `--build-timeout`.
+
+mod common;
+
+use common::Server;
+
+#[test]
+fn build_timeout() {
+    let server = Server::start("build-timeout", &["--build-timeout", "0"]);
+
+    let page = server.get("/");
+    assert_eq!(page.status, 500);
+    assert!(page.body.contains("and was killed"));
+}
@@ -0,0 +1,87 @@
+//! The caching headers the documentation is served with, and how conditional requests for it are
+//! answered.
+
+mod common;
+
+use std::fs;
+
+use common::Server;
+
+/// Finds a file in `static.files/`, whose names rustdoc hashes.
+///
+/// Arguments:
+/// * `server` - The server documenting the fixture.
+/// * `extension` - The extension of the file to find, like `.css`.
+///
+/// Returns:
+/// [`String`] -> The file's name.
+fn hashed_file(server: &Server, extension: &str) -> String {
+    fs::read_dir(server.root.join("target/doc/static.files"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .find(|name| name.ends_with(extension))
+        .unwrap_or_else(|| panic!("rustdoc didn't generate any `{extension}` file!"))
+}
+
+#[test]
+fn caching() {
+    let server = Server::start("caching", &[]);
+
+    // Pages change with every build, so they're always revalidated and never answered with `304`.
+    let page = server.get("/fixture/index.html");
+    assert_eq!(page.status, 200);
+    assert_eq!(page.header("cache-control"), Some("no-cache"));
+    assert_eq!(page.header("etag"), None);
+    assert_eq!(page.header("last-modified"), None);
+
+    let page = server.request(
+        "GET",
+        "/fixture/index.html",
+        &[("If-Modified-Since", "Fri, 01 Jan 2100 00:00:00 GMT")],
+    );
+    assert_eq!(page.status, 200);
+
+    // Hashed files are cached for good, with an `ETag` that doesn't change between requests.
+    let css = format!("/static.files/{}", hashed_file(&server, ".css"));
+    let asset = server.get(&css);
+    assert_eq!(asset.status, 200);
+    assert_eq!(
+        asset.header("cache-control"),
+        Some("public, max-age=31536000, immutable")
+    );
+
+    let etag = asset
+        .header("etag")
+        .expect("No `ETag` was sent!")
+        .to_string();
+    assert_eq!(server.get(&css).header("etag"), Some(etag.as_str()));
+    assert_eq!(
+        server.request("HEAD", &css, &[]).header("etag"),
+        Some(etag.as_str())
+    );
+
+    let revalidated = server.request("GET", &css, &[("If-None-Match", &etag)]);
+    assert_eq!(revalidated.status, 304);
+    assert_eq!(revalidated.header("etag"), Some(etag.as_str()));
+    assert_eq!(
+        revalidated.header("cache-control"),
+        Some("public, max-age=31536000, immutable")
+    );
+    assert!(revalidated.body.is_empty());
+
+    let changed = server.request("GET", &css, &[("If-None-Match", "W/\"other\"")]);
+    assert_eq!(changed.status, 200);
+
+    let last_modified = asset.header("last-modified").unwrap();
+    let revalidated = server.request("GET", &css, &[("If-Modified-Since", last_modified)]);
+    assert_eq!(revalidated.status, 304);
+
+    // Other files can change between builds, so they're revalidated on every use.
+    let other = server.get("/crates.js");
+    assert_eq!(other.status, 200);
+    assert_eq!(other.header("cache-control"), Some("no-cache"));
+
+    let etag = other.header("etag").expect("No `ETag` was sent!");
+    let revalidated = server.request("GET", "/crates.js", &[("If-None-Match", etag)]);
+    assert_eq!(revalidated.status, 304);
+}
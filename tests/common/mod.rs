@@ -0,0 +1,458 @@
+//! Helpers for running the server against the fixture crate in `tests/fixture/`.
+
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::{fs, thread};
+
+/// How long to wait for the fixture's documentation to be built.
+const BUILD_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// A running server, killed once dropped.
+pub struct Server {
+    child: Child,
+    /// The port the server listens at, on `127.0.0.1`.
+    pub port: u16,
+    /// The copy of the fixture crate the server documents.
+    pub root: PathBuf,
+    /// What the server wrote to its standard output so far.
+    output: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Server {
+    /// Copies the fixture crate and serves its documentation, waiting for the initial build to
+    /// finish.
+    ///
+    /// Arguments:
+    /// * `name` - The name of the copy, unique to the test.
+    /// * `args` - Extra arguments to pass to the server.
+    ///
+    /// Returns:
+    /// [`Server`] -> The running server.
+    pub fn start(name: &str, args: &[&str]) -> Self {
+        Self::start_with_env(name, args, &[])
+    }
+
+    /// Like [`Server::start`], with extra environment variables set for the server.
+    ///
+    /// Arguments:
+    /// * `name` - The name of the copy, unique to the test.
+    /// * `args` - Extra arguments to pass to the server.
+    /// * `envs` - The environment variables to set, as names and values.
+    ///
+    /// Returns:
+    /// [`Server`] -> The running server.
+    pub fn start_with_env(name: &str, args: &[&str], envs: &[(&str, &str)]) -> Self {
+        let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+        copy_fixture(&root);
+
+        Self::spawn(root, args, envs, true)
+    }
+
+    /// Like [`Server::start`], without `--quiet`, so that everything is logged.
+    ///
+    /// Arguments:
+    /// * `name` - The name of the copy, unique to the test.
+    /// * `args` - Extra arguments to pass to the server.
+    ///
+    /// Returns:
+    /// [`Server`] -> The running server.
+    pub fn start_logged(name: &str, args: &[&str]) -> Self {
+        let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+        copy_fixture(&root);
+
+        Self::spawn(root, args, &[], false)
+    }
+
+    /// Like [`Server::start`], changing the copy of the fixture before the server starts.
+    ///
+    /// Arguments:
+    /// * `name` - The name of the copy, unique to the test.
+    /// * `args` - Extra arguments to pass to the server.
+    /// * `prepare` - Changes the copy, given its root.
+    ///
+    /// Returns:
+    /// [`Server`] -> The running server.
+    pub fn start_prepared(name: &str, args: &[&str], prepare: impl FnOnce(&Path)) -> Self {
+        let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+        copy_fixture(&root);
+        prepare(&root);
+
+        Self::spawn(root, args, &[], true)
+    }
+
+    /// Like [`Server::start`], without waiting for the initial build to finish.
+    ///
+    /// Arguments:
+    /// * `name` - The name of the copy, unique to the test.
+    /// * `args` - Extra arguments to pass to the server.
+    ///
+    /// Returns:
+    /// [`Server`] -> The running server, answering while it builds.
+    pub fn start_building(name: &str, args: &[&str]) -> Self {
+        let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+        copy_fixture(&root);
+
+        Self::launch(root, args, &[], true)
+    }
+
+    /// Starts a server documenting a copy of the fixture, waiting for the initial build to finish.
+    ///
+    /// Arguments:
+    /// * `root` - The copy of the fixture.
+    /// * `args` - Extra arguments to pass to the server.
+    /// * `envs` - The environment variables to set, as names and values.
+    /// * `quiet` - Whether to only log warnings and errors, with `--quiet`.
+    ///
+    /// Returns:
+    /// [`Server`] -> The running server.
+    fn spawn(root: PathBuf, args: &[&str], envs: &[(&str, &str)], quiet: bool) -> Self {
+        let server = Self::launch(root, args, envs, quiet);
+        server.wait_until_built();
+        server
+    }
+
+    /// Starts a server documenting a copy of the fixture, waiting until it listens.
+    ///
+    /// Arguments:
+    /// * `root` - The copy of the fixture.
+    /// * `args` - Extra arguments to pass to the server.
+    /// * `envs` - The environment variables to set, as names and values.
+    /// * `quiet` - Whether to only log warnings and errors, with `--quiet`.
+    ///
+    /// Returns:
+    /// [`Server`] -> The running server.
+    fn launch(root: PathBuf, args: &[&str], envs: &[(&str, &str)], quiet: bool) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_docs"))
+            .current_dir(&root)
+            .args(["--bind", "127.0.0.1:0"])
+            .args(quiet.then_some("--quiet"))
+            .args(args)
+            .envs(envs.iter().copied())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Could not start the server!");
+
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+
+        // With `--events-to-stdout`, it's announced by the `listening` event instead.
+        let port = line
+            .trim()
+            .strip_prefix("DOCS_SERVER_PORT=")
+            .and_then(|port| port.parse().ok())
+            .or_else(|| {
+                let event: serde_json::Value = serde_json::from_str(&line).ok()?;
+                event["port"].as_u64()?.try_into().ok()
+            })
+            .unwrap_or_else(|| panic!("Unexpected output from the server: {line:?}"));
+
+        // The rest is read as it's written, so that the server never blocks on a full pipe. Bytes
+        // are kept as they come, since not everything written ends with a newline.
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let written = output.clone();
+
+        thread::spawn(move || {
+            let mut buffer = [0; 4096];
+
+            while let Ok(read @ 1..) = stdout.read(&mut buffer) {
+                written.lock().unwrap().extend_from_slice(&buffer[..read]);
+            }
+        });
+
+        Self {
+            child,
+            port,
+            root,
+            output,
+        }
+    }
+
+    /// Waits until `/` stops serving the page shown while the documentation is being built.
+    pub fn wait_until_built(&self) {
+        let start = Instant::now();
+
+        while self.get("/").body.contains("Building documentation...") {
+            assert!(
+                start.elapsed() < BUILD_TIMEOUT,
+                "The fixture didn't build in time!"
+            );
+            thread::sleep(Duration::from_millis(200));
+        }
+    }
+
+    /// What the server wrote to its standard output after announcing its port.
+    ///
+    /// Returns:
+    /// [`String`] -> The output so far.
+    pub fn output(&self) -> String {
+        String::from_utf8_lossy(&self.output.lock().unwrap()).into_owned()
+    }
+
+    /// The ID of the server's process.
+    ///
+    /// Returns:
+    /// [`u32`] -> The process ID.
+    pub fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Asks the server to shut down with `SIGTERM`, and waits for it to exit.
+    #[cfg(unix)]
+    pub fn terminate(&mut self) {
+        Command::new("kill")
+            .args(["-TERM", &self.child.id().to_string()])
+            .status()
+            .unwrap();
+
+        self.child.wait().unwrap();
+    }
+
+    /// Sends a `GET` request.
+    ///
+    /// Arguments:
+    /// * `path` - The path to request.
+    ///
+    /// Returns:
+    /// [`Response`] -> The response.
+    pub fn get(&self, path: &str) -> Response {
+        self.request("GET", path, &[])
+    }
+
+    /// Sends a request, reading its whole response.
+    ///
+    /// Arguments:
+    /// * `method` - The request's method.
+    /// * `path` - The path to request.
+    /// * `headers` - The request's headers, other than `Host` and `Connection`.
+    ///
+    /// Returns:
+    /// [`Response`] -> The response.
+    pub fn request(&self, method: &str, path: &str, headers: &[(&str, &str)]) -> Response {
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port)).unwrap();
+
+        let mut request =
+            format!("{method} {path} HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n");
+
+        for (name, value) in headers {
+            request.push_str(&format!("{name}: {value}\r\n"));
+        }
+
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).unwrap();
+
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw).unwrap();
+
+        Response::parse(&raw)
+    }
+}
+
+impl Server {
+    /// Connects to the reload WebSocket, like a page served by the server does.
+    ///
+    /// Returns:
+    /// [`WebSocket`] -> The connection, once the upgrade was accepted.
+    pub fn websocket(&self) -> WebSocket {
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port)).unwrap();
+        stream.set_read_timeout(Some(BUILD_TIMEOUT)).unwrap();
+
+        stream
+            .write_all(
+                b"GET /__reload HTTP/1.1\r\nHost: 127.0.0.1\r\nUpgrade: websocket\r\n\
+                  Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                  Sec-WebSocket-Version: 13\r\n\r\n",
+            )
+            .unwrap();
+
+        // Read a byte at a time, so that no message after the response's head is read with it.
+        let mut head = Vec::new();
+        let mut byte = [0];
+
+        while !head.ends_with(b"\r\n\r\n") {
+            stream.read_exact(&mut byte).unwrap();
+            head.push(byte[0]);
+        }
+
+        assert_eq!(Response::parse(&head).status, 101);
+        WebSocket { stream }
+    }
+}
+
+/// A connection to the reload WebSocket, closed once dropped.
+pub struct WebSocket {
+    stream: TcpStream,
+}
+
+impl WebSocket {
+    /// Reads the next text message, skipping pings.
+    ///
+    /// Returns:
+    /// [`String`] -> The message.
+    pub fn message(&mut self) -> String {
+        loop {
+            let (opcode, payload) = self.frame();
+
+            if opcode == 0x1 {
+                return String::from_utf8(payload).unwrap();
+            }
+        }
+    }
+
+    /// Reads the next frame, whether it's a message or a ping.
+    ///
+    /// Returns:
+    /// [`(u8, Vec<u8>)`] -> The frame's opcode and payload.
+    pub fn frame(&mut self) -> (u8, Vec<u8>) {
+        let mut head = [0; 2];
+        self.stream.read_exact(&mut head).unwrap();
+
+        // Messages from the server aren't masked.
+        let length = match head[1] & 0x7f {
+            126 => {
+                let mut length = [0; 2];
+                self.stream.read_exact(&mut length).unwrap();
+                u16::from_be_bytes(length) as usize
+            }
+            127 => {
+                let mut length = [0; 8];
+                self.stream.read_exact(&mut length).unwrap();
+                u64::from_be_bytes(length) as usize
+            }
+            length => length as usize,
+        };
+
+        let mut payload = vec![0; length];
+        self.stream.read_exact(&mut payload).unwrap();
+
+        (head[0] & 0x0f, payload)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+/// A response read off the wire.
+pub struct Response {
+    /// The response's status code.
+    pub status: u16,
+    /// The response's headers, with lowercase names.
+    pub headers: Vec<(String, String)>,
+    /// The response's body, which is only read when it isn't chunked.
+    pub body: String,
+}
+
+impl Response {
+    /// Parses a response.
+    ///
+    /// Arguments:
+    /// * `raw` - The bytes of the response.
+    ///
+    /// Returns:
+    /// [`Response`] -> The parsed response.
+    fn parse(raw: &[u8]) -> Self {
+        let raw = String::from_utf8_lossy(raw);
+        let (head, body) = raw.split_once("\r\n\r\n").unwrap_or((&raw, ""));
+        let mut lines = head.lines();
+
+        let status = lines
+            .next()
+            .and_then(|line| line.split(' ').nth(1))
+            .and_then(|status| status.parse().ok())
+            .unwrap_or_else(|| panic!("Malformed response: {raw:?}"));
+
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+
+        Self {
+            status,
+            headers,
+            body: body.to_string(),
+        }
+    }
+
+    /// A header of the response.
+    ///
+    /// Arguments:
+    /// * `name` - The header's lowercase name.
+    ///
+    /// Returns:
+    /// [`Option<&str>`] -> The header's value, if it was sent.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Waits until a condition holds, failing the test if it takes too long.
+///
+/// Arguments:
+/// * `what` - What's being waited for, to name it if it takes too long.
+/// * `condition` - The condition.
+pub fn wait_until(what: &str, mut condition: impl FnMut() -> bool) {
+    let start = Instant::now();
+
+    while !condition() {
+        assert!(
+            start.elapsed() < BUILD_TIMEOUT,
+            "Timed out waiting until {what}!"
+        );
+        thread::sleep(Duration::from_millis(200));
+    }
+}
+
+/// How many builds were written to a build log.
+///
+/// Arguments:
+/// * `path` - The path of the build log.
+///
+/// Returns:
+/// [`usize`] -> How many lines it has.
+pub fn builds(path: &Path) -> usize {
+    fs::read_to_string(path).unwrap_or_default().lines().count()
+}
+
+/// Copies the fixture crate, replacing its sources if it was copied before.
+///
+/// The generated documentation is kept between runs, so that it's not rebuilt from scratch.
+///
+/// Arguments:
+/// * `to` - Where to copy it to.
+pub fn copy_fixture(to: &Path) {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixture");
+
+    for dir in ["src", "dep/src"] {
+        fs::remove_dir_all(to.join(dir)).ok();
+    }
+
+    for dir in ["src/bin", "dep/src"] {
+        fs::create_dir_all(to.join(dir)).unwrap();
+    }
+
+    for file in [
+        "Cargo.toml",
+        "src/lib.rs",
+        "src/bin/tool.rs",
+        "dep/Cargo.toml",
+        "dep/src/lib.rs",
+    ] {
+        fs::copy(fixture.join(file), to.join(file)).unwrap();
+    }
+}
@@ -0,0 +1,50 @@
+//! Compressing responses with `--compress`.
+
+mod common;
+
+use std::fs;
+
+use common::Server;
+
+#[test]
+fn compresses_text_but_not_tiny_files() {
+    let server = Server::start(
+        "compression",
+        &["--compress", "--min-compress-size", "4000"],
+    );
+
+    let static_file = |prefix: &str, extension: &str| {
+        let name = fs::read_dir(server.root.join("target/doc/static.files"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .find(|name| name.starts_with(prefix) && name.ends_with(extension))
+            .unwrap_or_else(|| panic!("rustdoc didn't generate {prefix}*{extension}!"));
+        let size = fs::metadata(server.root.join("target/doc/static.files").join(&name))
+            .unwrap()
+            .len();
+
+        (format!("/static.files/{name}"), size)
+    };
+
+    let encoding = |path: &str| {
+        let response = server.request("GET", path, &[("Accept-Encoding", "gzip")]);
+        assert_eq!(response.status, 200);
+        response.header("content-encoding").map(str::to_string)
+    };
+
+    // The search index's script is big enough to always be compressed.
+    let (search, _) = static_file("search-", ".js");
+    assert_eq!(encoding(&search).as_deref(), Some("gzip"));
+
+    // SVGs are compressed like text, unless they're too small to bother.
+    for (svg, size) in [
+        static_file("favicon-", ".svg"),
+        static_file("rust-logo-", ".svg"),
+    ] {
+        let expected = (size > 4000).then_some("gzip");
+        assert_eq!(encoding(&svg).as_deref(), expected, "{svg} is {size} bytes");
+    }
+
+    let (png, _) = static_file("favicon-32x32-", ".png");
+    assert_eq!(encoding(&png), None);
+}
@@ -0,0 +1,25 @@
+//! The content types rustdoc's files are served with.
+
+mod common;
+
+use std::fs;
+
+use common::Server;
+
+#[test]
+fn content_types() {
+    let server = Server::start("content-types", &[]);
+
+    let font = fs::read_dir(server.root.join("target/doc/static.files"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .find(|name| name.ends_with(".woff2"))
+        .expect("rustdoc didn't generate any font!");
+
+    let font = server.get(&format!("/static.files/{font}"));
+    assert_eq!(font.status, 200);
+    assert_eq!(font.header("content-type"), Some("font/woff2"));
+
+    let page = server.get("/fixture/index.html");
+    assert_eq!(page.header("content-type"), Some("text/html"));
+}
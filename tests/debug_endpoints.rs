@@ -0,0 +1,75 @@
+//! Inspecting what the server resolved its options to with `--debug-endpoints`.
+
+mod common;
+
+use std::{env, fs};
+
+use common::Server;
+use serde_json::Value;
+
+#[test]
+fn serves_config() {
+    let server = Server::start("debug-endpoints", &["--debug-endpoints"]);
+
+    let response = server.get("/__debug/config");
+    assert_eq!(response.status, 200);
+
+    let config: Value = serde_json::from_str(&response.body).unwrap();
+    let root = fs::canonicalize(&server.root).unwrap();
+
+    assert_eq!(config["root"], root.to_str().unwrap());
+    assert_eq!(config["packages"], serde_json::json!(["fixture"]));
+    assert_eq!(config["doc_dir"], root.join("target/doc").to_str().unwrap());
+    assert_eq!(config["watching"], "events");
+    assert!(
+        config["watched"]
+            .as_array()
+            .unwrap()
+            .contains(&root.join("src").to_str().unwrap().into())
+    );
+    assert!(
+        config["command"]
+            .as_str()
+            .unwrap()
+            .starts_with("cargo doc --no-deps --package fixture")
+    );
+}
+
+#[test]
+fn quotes_command_for_shells() {
+    let target = env::temp_dir().join("docs-server-debug $HOME;&|*`x`");
+    let server = Server::start(
+        "debug-endpoints-quoted",
+        &[
+            "--debug-endpoints",
+            "--target-dir",
+            target.to_str().unwrap(),
+        ],
+    );
+
+    let config: Value = serde_json::from_str(&server.get("/__debug/config").body).unwrap();
+
+    assert!(
+        config["command"]
+            .as_str()
+            .unwrap()
+            .contains(&format!(" --target-dir '{}'", target.display()))
+    );
+}
+
+#[test]
+fn off_by_default() {
+    let server = Server::start("debug-endpoints-off", &[]);
+    assert_eq!(server.get("/__debug/config").status, 404);
+}
+
+#[test]
+fn passes_jobs_to_cargo() {
+    for flag in ["--jobs", "-j"] {
+        let server = Server::start("debug-endpoints-jobs", &["--debug-endpoints", flag, "3"]);
+
+        let config: Value = serde_json::from_str(&server.get("/__debug/config").body).unwrap();
+
+        assert!(config["command"].as_str().unwrap().contains(" --jobs 3"));
+    }
+}
@@ -0,0 +1,31 @@
+//! Failing builds that have warnings with `--deny-warnings`.
+
+mod common;
+
+use std::fs;
+
+use common::Server;
+
+#[test]
+fn deny_warnings() {
+    let server = Server::start(
+        "deny-warnings",
+        &["--deny-warnings", "--no-watch", "--allow-remote-rebuild"],
+    );
+
+    let lib = server.root.join("src/lib.rs");
+    let source = fs::read_to_string(&lib).unwrap();
+    fs::write(
+        &lib,
+        format!("{source}\n/// Links to [`Missing`].\npub struct Broken;\n"),
+    )
+    .unwrap();
+
+    let failed = server.request("POST", "/__rebuild", &[]);
+    assert_eq!(failed.status, 500);
+    assert!(failed.body.contains("unresolved link"));
+
+    let page = server.get("/fixture/index.html");
+    assert_eq!(page.status, 500);
+    assert!(page.body.contains("failed"));
+}
@@ -0,0 +1,25 @@
+//! Redirecting requests for directories to their path with a trailing slash.
+
+mod common;
+
+use common::Server;
+
+#[test]
+fn redirects_to_trailing_slash() {
+    let server = Server::start("directories", &[]);
+
+    let directory = server.get("/fixture");
+    assert_eq!(directory.status, 307);
+    assert_eq!(directory.header("location"), Some("/fixture/"));
+    assert_eq!(server.get("/fixture/").status, 200);
+}
+
+#[test]
+fn redirects_through_stripped_prefix() {
+    let server = Server::start("directories-prefix", &["--strip-prefix", "/docs"]);
+
+    let directory = server.get("/docs/fixture");
+    assert_eq!(directory.status, 307);
+    assert_eq!(directory.header("location"), Some("/docs/fixture/"));
+    assert_eq!(server.get("/docs/fixture/").status, 200);
+}
@@ -0,0 +1,30 @@
+//! Displaying the private items of dependencies with `--doc-private-deps`.
+
+mod common;
+
+use std::fs;
+
+use common::Server;
+
+#[test]
+fn only_dependencies_show_private_items() {
+    let server = Server::start_prepared(
+        "doc-private-deps",
+        &["--doc-dep", "dep", "--doc-private-deps"],
+        |root| {
+            for (file, item) in [("dep/src/lib.rs", "DepHidden"), ("src/lib.rs", "Hidden")] {
+                let source = fs::read_to_string(root.join(file)).unwrap();
+                fs::write(
+                    root.join(file),
+                    format!("{source}\n/// A private item.\n#[allow(dead_code)]\nstruct {item};\n"),
+                )
+                .unwrap();
+            }
+        },
+    );
+
+    assert_eq!(server.get("/dep/struct.DepItem.html").status, 200);
+    assert_eq!(server.get("/dep/struct.DepHidden.html").status, 200);
+    assert_eq!(server.get("/fixture/struct.Item.html").status, 200);
+    assert_eq!(server.get("/fixture/struct.Hidden.html").status, 404);
+}
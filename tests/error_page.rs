@@ -0,0 +1,58 @@
+//! The page served in place of the documentation while the build is failing.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use common::{Server, copy_fixture, wait_until};
+
+#[test]
+fn colors_diagnostics() {
+    let server = Server::start("error-page", &[]);
+
+    let lib = server.root.join("src/lib.rs");
+    let source = fs::read_to_string(&lib).unwrap();
+    fs::write(&lib, format!("{source}\npub use nothing::Here;\n")).unwrap();
+
+    wait_until("the error page is served", || {
+        server
+            .get("/fixture/index.html")
+            .body
+            .contains("<code>cargo doc</code> failed")
+    });
+
+    let page = server.get("/fixture/index.html").body;
+    assert!(page.contains("prefers-color-scheme: dark"));
+
+    // It reloads once the build is fixed, like the documentation's pages.
+    assert!(page.contains(r#"<script data-path="/__reload""#));
+    assert!(page.contains(r#"<span class="ansi-bold ansi-9">error[E0432]</span>"#));
+    assert!(!page.contains('\x1b'));
+
+    // Locations still point at absolute paths, even with the colors around them.
+    let absolute = fs::canonicalize(&lib).unwrap();
+    assert!(page.contains(&format!("{}:", absolute.display())));
+}
+
+#[test]
+fn fail_fast_never_serves() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("error-page-fail-fast");
+    copy_fixture(&root);
+
+    let lib = root.join("src/lib.rs");
+    let source = fs::read_to_string(&lib).unwrap();
+    fs::write(&lib, format!("{source}\npub use nothing::Here;\n")).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(&root)
+        .args(["--bind", "127.0.0.1:0", "--quiet", "--fail-fast"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(4));
+
+    // The port is only announced once the server is bound.
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("DOCS_SERVER_PORT="));
+}
@@ -0,0 +1,70 @@
+//! Writing build events to the standard output with `--events-to-stdout`.
+
+mod common;
+
+use std::fs;
+
+use common::{Server, wait_until};
+use serde_json::Value;
+
+/// The `build_started` events the server wrote so far.
+///
+/// Arguments:
+/// * `server` - The server, started with `--events-to-stdout`.
+///
+/// Returns:
+/// [`Vec<Value>`] -> The events, in the order they were written.
+fn builds_started(server: &Server) -> Vec<Value> {
+    server
+        .output()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|event| event["event"] == "build_started")
+        .collect()
+}
+
+#[test]
+fn reports_changed_paths() {
+    let server = Server::start("events", &["--events-to-stdout"]);
+
+    let lib = server.root.join("src/lib.rs");
+    let source = fs::read_to_string(&lib).unwrap();
+    fs::write(
+        &lib,
+        format!("{source}\n/// Added later.\npub struct Added;\n"),
+    )
+    .unwrap();
+
+    wait_until("the change is reported", || {
+        builds_started(&server).len() == 2
+    });
+
+    let started = &builds_started(&server)[1];
+    assert_eq!(started["trigger"], "change");
+    assert_eq!(started["changed"], serde_json::json!(["src/lib.rs"]));
+    assert_eq!(started["changed_count"], 1);
+}
+
+#[test]
+fn truncates_many_changed_paths() {
+    // Created beforehand, so that only the files are changes.
+    let server = Server::start_prepared(
+        "events-many",
+        &["--events-to-stdout", "--debounce", "1000"],
+        |root| fs::create_dir_all(root.join("src/generated")).unwrap(),
+    );
+
+    let generated = server.root.join("src/generated");
+
+    for i in 0..60 {
+        fs::write(generated.join(format!("file{i}.rs")), "").unwrap();
+    }
+
+    wait_until("the changes are reported", || {
+        builds_started(&server).len() == 2
+    });
+
+    let started = &builds_started(&server)[1];
+    assert_eq!(started["changed"].as_array().unwrap().len(), 50);
+    assert_eq!(started["changed_count"], 60);
+}
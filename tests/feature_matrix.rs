@@ -0,0 +1,38 @@
+//! Serving the documentation built with each set of features given with `--feature-set`.
+
+mod common;
+
+use common::{Server, wait_until};
+
+#[test]
+fn feature_matrix() {
+    let server = Server::start(
+        "feature-matrix",
+        &[
+            "--feature-set",
+            "",
+            "--feature-set",
+            "extra",
+            "--feature-set",
+            "extra,broken",
+        ],
+    );
+
+    wait_until("every feature set built", || {
+        !server.get("/").body.contains("building")
+    });
+
+    let index = server.get("/").body;
+    assert!(index.contains(r#"<a href="default/">default</a> <span class="kind">built</span>"#));
+    assert!(index.contains(r#"<a href="extra/">extra</a> <span class="kind">built</span>"#));
+    assert!(
+        index.contains(
+            r#"<a href="extra+broken/">extra+broken</a> <span class="kind">failed</span>"#
+        )
+    );
+
+    assert_eq!(server.get("/default/fixture/struct.Item.html").status, 200);
+    assert_eq!(server.get("/default/fixture/struct.Extra.html").status, 404);
+    assert_eq!(server.get("/extra/fixture/struct.Extra.html").status, 200);
+    assert_eq!(server.get("/extra+broken/fixture/index.html").status, 500);
+}
@@ -0,0 +1,4 @@
+//! A dependency of the fixture, checked out next to it.
+
+/// An item of the dependency.
+pub struct DepItem;
@@ -0,0 +1,3 @@
+//! A binary of the fixture, documented alongside its library.
+
+fn main() {}
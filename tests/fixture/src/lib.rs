@@ -0,0 +1,11 @@
+//! A tiny crate whose documentation the integration tests serve.
+
+/// A documented item.
+pub struct Item;
+
+/// An item only documented with the `extra` feature.
+#[cfg(feature = "extra")]
+pub struct Extra;
+
+#[cfg(feature = "broken")]
+compile_error!("The `broken` feature doesn't build.");
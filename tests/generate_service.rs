@@ -0,0 +1,121 @@
+//! Printing service files with `docs generate-service`.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Runs the binary from a directory of its own, without starting a server.
+///
+/// Arguments:
+/// * `args` - The arguments to run it with.
+///
+/// Returns:
+/// [`Output`] -> What it printed, and how it exited.
+fn run(args: &[&str]) -> Output {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("generate-service");
+    fs::create_dir_all(&dir).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(&dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn systemd_with_socket() {
+    let output = run(&[
+        "--package",
+        "fixture",
+        "--bind",
+        "127.0.0.1:9000",
+        "my crate",
+        "generate-service",
+        "systemd",
+        "--socket-activation",
+    ]);
+    assert!(output.status.success());
+
+    let unit = String::from_utf8(output.stdout).unwrap();
+    let binary = fs::canonicalize(env!("CARGO_BIN_EXE_docs")).unwrap();
+    let dir =
+        fs::canonicalize(Path::new(env!("CARGO_TARGET_TMPDIR")).join("generate-service")).unwrap();
+
+    assert!(unit.contains("ListenStream=127.0.0.1:9000\n"));
+    assert!(unit.contains(&format!(
+        "ExecStart={} --package fixture --bind 127.0.0.1:9000 \"my crate\"\n",
+        binary.display()
+    )));
+    assert!(unit.contains(&format!("WorkingDirectory={}\n", dir.display())));
+}
+
+#[test]
+fn launchd() {
+    let output = run(&["--localhost", "generate-service", "launchd"]);
+    assert!(output.status.success());
+
+    let plist = String::from_utf8(output.stdout).unwrap();
+    assert!(plist.contains("<string>--localhost</string>"));
+    assert!(!plist.contains("<string>generate-service</string>"));
+
+    let refused = run(&["generate-service", "launchd", "--socket-activation"]);
+    assert_eq!(refused.status.code(), Some(2));
+}
+
+#[test]
+fn systemd_socket_on_interface() {
+    let output = run(&[
+        "--bind",
+        "0.0.0.0:9000",
+        "--interface",
+        "eth0",
+        "generate-service",
+        "systemd",
+        "--socket-activation",
+    ]);
+    assert!(output.status.success());
+
+    let unit = String::from_utf8(output.stdout).unwrap();
+    assert!(unit.contains("ListenStream=0.0.0.0:9000\nBindToDevice=eth0\n"));
+}
+
+#[test]
+fn systemd_socket_on_localhost() {
+    let output = run(&[
+        "--bind",
+        "0.0.0.0:9000",
+        "--bind",
+        "[::]:9000",
+        "--localhost",
+        "generate-service",
+        "systemd",
+        "--socket-activation",
+    ]);
+    assert!(output.status.success());
+
+    let unit = String::from_utf8(output.stdout).unwrap();
+    assert!(unit.contains("ListenStream=127.0.0.1:9000\nListenStream=[::1]:9000\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn skips_non_utf8_environment() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("generate-service");
+    fs::create_dir_all(&dir).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(&dir)
+        .args(["generate-service", "systemd"])
+        .env("DOCS_SERVER_INVALID", OsStr::from_bytes(b"\xff"))
+        .env("DOCS_SERVER_RELOAD_PING", "5")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let unit = String::from_utf8(output.stdout).unwrap();
+    assert!(unit.contains("Environment=DOCS_SERVER_RELOAD_PING=5\n"));
+    assert!(!unit.contains("DOCS_SERVER_INVALID"));
+}
@@ -0,0 +1,25 @@
+//! Answering health checks at `/__health`, like from uptime monitors.
+
+mod common;
+
+use common::Server;
+
+#[test]
+fn answers_head_requests() {
+    let server = Server::start("health", &[]);
+
+    let health = server.get("/__health");
+    assert_eq!(health.status, 200);
+    assert_eq!(health.body, "OK");
+
+    let health = server.request("HEAD", "/__health", &[]);
+    assert_eq!(health.status, 200);
+    assert_eq!(health.header("content-length"), Some("2"));
+    assert_eq!(health.body, "");
+
+    // Pages answer them like `GET` requests too.
+    let page = server.request("HEAD", "/fixture/index.html", &[]);
+    assert_eq!(page.status, 200);
+    assert_eq!(page.header("content-type"), Some("text/html"));
+    assert_eq!(page.body, "");
+}
@@ -0,0 +1,50 @@
+//! Changes to files matched by `--ignore-file` not triggering rebuilds.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use common::{Server, builds, wait_until};
+
+#[test]
+fn ignore_file() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("ignore-file");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join(".docsignore"), "src/generated/\n").unwrap();
+    fs::remove_file(root.join("builds.jsonl")).ok();
+
+    let server = Server::start(
+        "ignore-file",
+        &[
+            "--ignore-file",
+            ".docsignore",
+            "--ignore-file",
+            ".missing",
+            "--build-log",
+            "builds.jsonl",
+        ],
+    );
+    let log = server.root.join("builds.jsonl");
+    assert_eq!(builds(&log), 1);
+
+    fs::create_dir_all(server.root.join("src/generated")).unwrap();
+    fs::write(server.root.join("src/generated/data.rs"), "// Generated.\n").unwrap();
+
+    thread::sleep(Duration::from_secs(2));
+    assert_eq!(builds(&log), 1);
+
+    let lib = server.root.join("src/lib.rs");
+    let source = fs::read_to_string(&lib).unwrap();
+    fs::write(
+        &lib,
+        format!("{source}\n/// Added later.\npub struct Added;\n"),
+    )
+    .unwrap();
+
+    wait_until("the sources changing rebuilds the documentation", || {
+        builds(&log) == 2
+    });
+}
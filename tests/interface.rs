@@ -0,0 +1,63 @@
+//! Binding to the address of a network interface with `--interface`.
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::io::{BufRead, BufReader};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use common::copy_fixture;
+use serde_json::Value;
+
+#[test]
+fn binds_to_interface() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("interface");
+    copy_fixture(&root);
+
+    // The wildcard address of `--bind` is replaced with the loopback interface's.
+    let mut child = Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(&root)
+        .args([
+            "--bind",
+            "0.0.0.0:0",
+            "--interface",
+            "lo",
+            "--events-to-stdout",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let event: Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(event["event"], "listening");
+
+    let address: SocketAddr = event["address"].as_str().unwrap().parse().unwrap();
+    assert_eq!(address.ip(), Ipv4Addr::LOCALHOST);
+}
+
+#[test]
+fn refuses_unknown_interface() {
+    let output = Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(env!("CARGO_TARGET_TMPDIR"))
+        .args(["--bind", "127.0.0.1:0", "--interface", "nonexistent0"])
+        .stdout(Stdio::null())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Unknown network interface `nonexistent0`"));
+    assert!(stderr.contains("`lo`"));
+}
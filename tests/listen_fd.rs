@@ -0,0 +1,119 @@
+//! Listening on the sockets passed down through systemd's socket activation.
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::fd::AsRawFd;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use std::{fs, thread};
+
+use common::copy_fixture;
+
+#[test]
+fn keeps_sockets_from_builds() {
+    keeps_sockets("listen-fd", "");
+}
+
+#[test]
+fn keeps_sockets_from_fail_fast_builds() {
+    // The initial build runs before the server listens on the sockets.
+    keeps_sockets("listen-fd-fail-fast", "--fail-fast");
+}
+
+/// Passes a socket to the server like systemd does, checking that the pre-build command doesn't
+/// inherit it, nor the variables telling about it.
+///
+/// Arguments:
+/// * `name` - The name of the fixture's copy, unique to the test.
+/// * `flags` - More arguments to pass to the server, separated by spaces.
+fn keeps_sockets(name: &str, flags: &str) {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    copy_fixture(&root);
+
+    // Left behind by earlier runs.
+    for file in ["pre-build-env", "pre-build-fds"] {
+        fs::remove_file(root.join(file)).ok();
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let fd = listener.as_raw_fd();
+
+    // The shell passes the socket on as the file descriptor 3, the first one systemd passes, and
+    // is replaced by the server so that `LISTEN_PID` is the server's.
+    let mut command = Command::new("sh");
+    command
+        .current_dir(&root)
+        .args([
+            "-c",
+            "LISTEN_PID=$$ LISTEN_FDS=1 exec \"$0\" --quiet --pre-build \"$1\" $2",
+            env!("CARGO_BIN_EXE_docs"),
+            "env > pre-build-env; ls /proc/$$/fd > pre-build-fds",
+            flags,
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    // SAFETY: `dup2` and `fcntl` are safe to call between forking and executing.
+    unsafe {
+        command.pre_exec(move || {
+            // `dup2` leaves a file descriptor that's already 3 closed on execution.
+            let passed = match fd {
+                3 => libc::fcntl(fd, libc::F_SETFD, 0),
+                _ => libc::dup2(fd, 3),
+            };
+
+            match passed {
+                -1 => Err(std::io::Error::last_os_error()),
+                _ => Ok(()),
+            }
+        });
+    }
+
+    let mut child = command.spawn().unwrap();
+    drop(listener);
+
+    let start = Instant::now();
+
+    while !root.join("pre-build-fds").exists() || !root.join("target/doc/fixture").exists() {
+        assert!(
+            start.elapsed() < Duration::from_secs(120),
+            "The fixture didn't build in time!"
+        );
+        thread::sleep(Duration::from_millis(200));
+    }
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream
+        .write_all(b"GET /__health HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let fdinfo = fs::read_to_string(format!("/proc/{}/fdinfo/3", child.id())).unwrap();
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200"));
+
+    // The socket is closed when the server starts other processes, which aren't told about it.
+    let flags = fdinfo
+        .lines()
+        .find_map(|line| line.strip_prefix("flags:"))
+        .map(|flags| i32::from_str_radix(flags.trim(), 8).unwrap())
+        .unwrap();
+    assert_ne!(flags & libc::O_CLOEXEC, 0);
+
+    let env = fs::read_to_string(root.join("pre-build-env")).unwrap();
+    assert!(!env.lines().any(|line| line.starts_with("LISTEN_")));
+
+    let fds = fs::read_to_string(root.join("pre-build-fds")).unwrap();
+    assert!(!fds.lines().any(|fd| fd == "3"));
+}
@@ -0,0 +1,23 @@
+//! Serving a page at `/` while the initial build runs.
+
+mod common;
+
+use common::Server;
+
+#[test]
+fn serves_loading_page_until_built() {
+    let server = Server::start_building("loading-page", &["--pre-build", "sleep 5"]);
+
+    let loading = server.get("/");
+    assert_eq!(loading.status, 200);
+    assert!(loading.body.contains("<h1>Building documentation...</h1>"));
+
+    // Reloaded by the reload client once the build finishes.
+    assert!(loading.body.contains(r#"<script data-path="/__reload""#));
+
+    server.wait_until_built();
+
+    let redirect = server.get("/");
+    assert_eq!(redirect.status, 307);
+    assert_eq!(redirect.header("location"), Some("/fixture/"));
+}
@@ -0,0 +1,49 @@
+//! Writing the logs to a file with `--log-file`.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{Server, wait_until};
+
+#[test]
+fn log_file() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("log-file");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("docs.log"), "Logged earlier\n").unwrap();
+
+    // The server is `--quiet`, so only warnings and errors are logged.
+    let server = Server::start(
+        "log-file",
+        &[
+            "--log-file",
+            "docs.log",
+            "--log-file-mode",
+            "rotate",
+            "--no-watch",
+            "--allow-remote-rebuild",
+        ],
+    );
+
+    let log = root.join("docs.log");
+    assert_eq!(
+        fs::read_to_string(root.join("docs.log.old")).unwrap(),
+        "Logged earlier\n"
+    );
+
+    let lib = server.root.join("src/lib.rs");
+    let source = fs::read_to_string(&lib).unwrap();
+    fs::write(&lib, format!("{source}\npub struct Broken\n")).unwrap();
+    assert_eq!(server.request("POST", "/__rebuild", &[]).status, 500);
+
+    wait_until("the failure is logged", || {
+        fs::read_to_string(&log)
+            .unwrap()
+            .contains("[ERROR] `cargo doc")
+    });
+
+    let logged = fs::read_to_string(&log).unwrap();
+    assert!(!logged.contains("[INFO]"));
+    assert!(!logged.contains('\x1b'));
+}
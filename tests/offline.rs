@@ -0,0 +1,65 @@
+//! Resolving the packages with `cargo metadata` as offline and frozen as the build.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use common::{Server, copy_fixture};
+
+/// Runs the server on a copy of the fixture until it fails.
+///
+/// Arguments:
+/// * `root` - The copy of the fixture.
+/// * `flag` - The flag to pass on to cargo.
+///
+/// Returns:
+/// [`Output`] -> What the server output.
+fn run(root: &Path, flag: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(root)
+        .args(["--bind", "127.0.0.1:0", "--quiet", flag])
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn offline_resolves_locally() {
+    let server = Server::start("offline", &["--offline"]);
+    assert_eq!(server.get("/fixture/index.html").status, 200);
+
+    // A dependency that isn't in the local registry can't be downloaded.
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("missing-dependency");
+    copy_fixture(&root);
+    fs::remove_file(root.join("Cargo.lock")).ok();
+
+    let manifest = fs::read_to_string(root.join("Cargo.toml")).unwrap();
+    let manifest = manifest.replace(
+        "[dependencies]\n",
+        "[dependencies]\ndocs-server-missing-crate = \"1\"\n",
+    );
+    fs::write(root.join("Cargo.toml"), manifest).unwrap();
+
+    let output = run(&root, "--offline");
+    assert_eq!(output.status.code(), Some(3));
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Failed to get cargo metadata"));
+    assert!(stderr.contains("--offline"));
+}
+
+#[test]
+fn frozen_never_writes_lock_file() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("frozen");
+    copy_fixture(&root);
+    fs::remove_file(root.join("Cargo.lock")).ok();
+
+    let output = run(&root, "--frozen");
+    assert_eq!(output.status.code(), Some(3));
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Failed to get cargo metadata"));
+    assert!(stderr.contains("--frozen"));
+    assert!(!root.join("Cargo.lock").exists());
+}
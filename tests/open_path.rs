@@ -0,0 +1,104 @@
+//! Opening the browser at a given page with `--open-path`.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use common::{Server, wait_until};
+
+/// Puts a stand-in for the browser on the `PATH`, writing down the URL it's asked to open.
+///
+/// Arguments:
+/// * `name` - The name of its directory, unique to the test.
+///
+/// Returns:
+/// [`(String, PathBuf)`] -> The `PATH` to run the server with, and the file the URL is written to.
+fn fake_browser(name: &str) -> (String, PathBuf) {
+    let bin = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let opened = bin.join("opened");
+    fs::create_dir_all(&bin).unwrap();
+    fs::remove_file(&opened).ok();
+
+    let opener = bin.join("xdg-open");
+    fs::write(
+        &opener,
+        format!("#!/bin/sh\nprintf '%s' \"$1\" > '{}'\n", opened.display()),
+    )
+    .unwrap();
+    fs::set_permissions(&opener, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = format!("{}:{}", bin.display(), std::env::var("PATH").unwrap());
+    (path, opened)
+}
+
+#[test]
+fn opens_path_with_fragment() {
+    let (path, opened) = fake_browser("open-path-bin");
+    let server = Server::start_with_env(
+        "open-path",
+        &["--open", "--open-path", "/fixture/index.html#structs"],
+        &[("PATH", &path)],
+    );
+
+    wait_until("the browser is opened", || {
+        fs::read_to_string(&opened).is_ok_and(|url| !url.is_empty())
+    });
+    assert_eq!(
+        fs::read_to_string(&opened).unwrap(),
+        format!(
+            "http://127.0.0.1:{}/fixture/index.html#structs",
+            server.port
+        )
+    );
+}
+
+#[test]
+fn reopens_path_after_rebuilds() {
+    let (path, opened) = fake_browser("open-path-rebuild-bin");
+    let server = Server::start_with_env(
+        "open-path-rebuild",
+        &[
+            "--open",
+            "--open-on-rebuild",
+            "--open-path",
+            "fixture/struct.Item.html",
+        ],
+        &[("PATH", &path)],
+    );
+
+    wait_until("the browser is opened", || {
+        fs::read_to_string(&opened).is_ok_and(|url| !url.is_empty())
+    });
+    fs::remove_file(&opened).unwrap();
+
+    let lib = server.root.join("src/lib.rs");
+    let source = fs::read_to_string(&lib).unwrap();
+    fs::write(&lib, format!("{source}\npub struct Added;\n")).unwrap();
+
+    wait_until("the browser is opened again", || {
+        fs::read_to_string(&opened).is_ok_and(|url| !url.is_empty())
+    });
+    assert_eq!(
+        fs::read_to_string(&opened).unwrap(),
+        format!("http://127.0.0.1:{}/fixture/struct.Item.html", server.port)
+    );
+}
+
+#[test]
+fn refuses_twice_encoded_path() {
+    let status = Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(env!("CARGO_TARGET_TMPDIR"))
+        .args(["--bind", "127.0.0.1:0", "--quiet", "--open"])
+        .args(["--open-path", "fixture/index.html%2523structs"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+
+    assert_eq!(status.code(), Some(2));
+}
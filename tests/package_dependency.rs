@@ -0,0 +1,27 @@
+//! Serving the documentation of a dependency checked out locally, without the crate's.
+
+mod common;
+
+use std::fs;
+
+use common::{Server, wait_until};
+
+#[test]
+fn documents_local_dependency() {
+    let server = Server::start("package-dependency", &["--package", "dep"]);
+
+    assert_eq!(server.get("/dep/struct.DepItem.html").status, 200);
+    assert_eq!(server.get("/fixture/index.html").status, 404);
+
+    let lib = server.root.join("dep/src/lib.rs");
+    let source = fs::read_to_string(&lib).unwrap();
+    fs::write(
+        &lib,
+        format!("{source}\n/// Added later.\npub struct Added;\n"),
+    )
+    .unwrap();
+
+    wait_until("the dependency's new item is served", || {
+        server.get("/dep/struct.Added.html").status == 200
+    });
+}
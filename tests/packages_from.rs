@@ -0,0 +1,71 @@
+//! Reading the packages to document from a list with `--packages-from`.
+
+mod common;
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use common::{Server, copy_fixture};
+
+#[test]
+fn documents_listed_packages() {
+    let list = Path::new(env!("CARGO_TARGET_TMPDIR")).join("packages.txt");
+    fs::write(&list, "fixture\n\n  dep\n").unwrap();
+
+    let server = Server::start(
+        "packages-from",
+        &["--packages-from", list.to_str().unwrap()],
+    );
+
+    assert_eq!(server.get("/fixture/index.html").status, 200);
+    assert_eq!(server.get("/dep/index.html").status, 200);
+}
+
+#[test]
+fn reports_unknown_packages_from_stdin() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("packages-from-stdin");
+    copy_fixture(&root);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(&root)
+        .args(["--bind", "127.0.0.1:0", "--packages-from", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"fixture\nnonexistent\n")
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Unknown package `nonexistent`!"));
+}
+
+#[test]
+fn reports_unknown_package_names() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("packages-unknown");
+    copy_fixture(&root);
+
+    // Even alongside one that's found, like `--package` typos.
+    let output = Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(&root)
+        .args(["--bind", "127.0.0.1:0", "--package", "fixture,fixtrue"])
+        .stdout(Stdio::null())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Unknown package `fixtrue`! The workspace members are `fixture`."));
+}
@@ -0,0 +1,67 @@
+//! Running a command before every build with `--pre-build`.
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use std::{fs, thread};
+
+use common::{copy_fixture, wait_until};
+
+/// Whether a process is still running, rather than gone or exited without being waited for.
+///
+/// Arguments:
+/// * `pid` - The ID of the process.
+///
+/// Returns:
+/// [`bool`] -> Whether it's running.
+fn running(pid: &str) -> bool {
+    fs::read_to_string(format!("/proc/{pid}/stat")).is_ok_and(|stat| {
+        // The state follows the name, which is in parentheses.
+        !stat
+            .rsplit_once(") ")
+            .is_some_and(|(_, rest)| rest.starts_with(['Z', 'X']))
+    })
+}
+
+#[test]
+fn stops_its_processes_with_server() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("pre-build-stopped");
+    copy_fixture(&root);
+    fs::remove_file(root.join("sleep-pid")).ok();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(&root)
+        .args(["--bind", "127.0.0.1:0", "--quiet"])
+        .args(["--pre-build", "sleep 60 & echo $! > sleep-pid; wait"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    wait_until("the pre-build command started", || {
+        fs::read_to_string(root.join("sleep-pid")).is_ok_and(|pid| pid.ends_with('\n'))
+    });
+
+    let pid = fs::read_to_string(root.join("sleep-pid")).unwrap();
+    let pid = pid.trim();
+    assert!(running(pid));
+
+    Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+
+    assert!(child.wait().unwrap().success());
+
+    // Killed along with the server, rather than once they're done.
+    let stopped = Instant::now();
+
+    while running(pid) {
+        assert!(stopped.elapsed() < Duration::from_secs(5));
+        thread::sleep(Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,90 @@
+//! Printing the directory the documentation is generated at with `--print-doc-dir`.
+
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+use std::{env, fs};
+
+use common::copy_fixture;
+
+/// Runs the server with `--print-doc-dir` on a copy of the fixture.
+///
+/// Arguments:
+/// * `name` - The name of the copy, unique to the test.
+/// * `args` - Extra arguments to pass to the server.
+///
+/// Returns:
+/// [`String`] -> What the server printed.
+fn print_doc_dir(name: &str, args: &[&str]) -> String {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    copy_fixture(&root);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(&root)
+        .arg("--print-doc-dir")
+        .args(args)
+        .env_remove("CARGO_TARGET_DIR")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn cargo_target_dir() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("print-doc-dir");
+    let printed = print_doc_dir("print-doc-dir", &[]);
+
+    assert_eq!(printed.trim(), root.join("target/doc").to_str().unwrap());
+}
+
+#[test]
+fn given_target_dir_and_target() {
+    let target = env::temp_dir().join("docs-server-print-doc-dir-test");
+    let printed = print_doc_dir(
+        "print-doc-dir-target",
+        &[
+            "--target-dir",
+            target.to_str().unwrap(),
+            "--target",
+            "x86_64-unknown-linux-gnu",
+        ],
+    );
+
+    assert_eq!(
+        printed.trim(),
+        target
+            .join("x86_64-unknown-linux-gnu/doc")
+            .to_str()
+            .unwrap()
+    );
+}
+
+#[test]
+fn configured_target_dir_of_manifest() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("print-doc-dir-config");
+    copy_fixture(&root);
+    fs::create_dir_all(root.join(".cargo")).unwrap();
+    fs::write(
+        root.join(".cargo/config.toml"),
+        "[build]\ntarget-dir = \"configured-target\"\n",
+    )
+    .unwrap();
+
+    // Found like cargo finds it when building, from the manifest's directory.
+    let output = Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(env!("CARGO_TARGET_TMPDIR"))
+        .args(["--print-doc-dir", "--manifest-path"])
+        .arg(root.join("Cargo.toml"))
+        .env_remove("CARGO_TARGET_DIR")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim(),
+        root.join("configured-target/doc").to_str().unwrap()
+    );
+}
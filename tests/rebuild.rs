@@ -0,0 +1,72 @@
+//! Rebuilds requested over HTTP, with the sources not being watched.
+
+mod common;
+
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+use common::Server;
+
+#[test]
+fn remote_rebuild() {
+    let server = Server::start("rebuild", &["--no-watch", "--allow-remote-rebuild"]);
+
+    assert_eq!(server.get("/__rebuild").status, 405);
+
+    let lib = server.root.join("src/lib.rs");
+    let source = fs::read_to_string(&lib).unwrap();
+    fs::write(
+        &lib,
+        format!("{source}\n/// Added later.\npub struct Added;\n"),
+    )
+    .unwrap();
+
+    // Nothing is watching the sources, so the change must not be picked up on its own.
+    thread::sleep(Duration::from_secs(2));
+    assert_eq!(server.get("/fixture/struct.Added.html").status, 404);
+
+    let rebuilt = server.request("POST", "/__rebuild", &[]);
+    assert_eq!(rebuilt.status, 200);
+    assert_eq!(server.get("/fixture/struct.Added.html").status, 200);
+
+    fs::write(&lib, format!("{source}\npub struct Broken\n")).unwrap();
+
+    let failed = server.request("POST", "/__rebuild", &[]);
+    assert_eq!(failed.status, 500);
+    assert!(failed.body.contains("cargo doc"));
+}
+
+#[test]
+fn rebuild_token() {
+    let server = Server::start(
+        "rebuild-token",
+        &["--allow-remote-rebuild", "--rebuild-token", "secret"],
+    );
+
+    assert_eq!(server.request("POST", "/__rebuild", &[]).status, 401);
+    assert_eq!(
+        server
+            .request("POST", "/__rebuild", &[("Authorization", "Bearer wrong")])
+            .status,
+        401
+    );
+    assert_eq!(
+        server
+            .request("POST", "/__rebuild", &[("Authorization", "Bearer secret")])
+            .status,
+        200
+    );
+}
+
+#[test]
+fn max_request_size() {
+    let server = Server::start(
+        "rebuild-size",
+        &["--allow-remote-rebuild", "--max-request-size", "1024"],
+    );
+
+    // Refused from its length alone, before the body is sent.
+    let refused = server.request("POST", "/__rebuild", &[("Content-Length", "4096")]);
+    assert_eq!(refused.status, 413);
+}
@@ -0,0 +1,95 @@
+//! Redirecting paths of the documentation elsewhere with `--redirect`.
+
+mod common;
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use common::{Server, copy_fixture};
+
+#[test]
+fn redirects_temporarily() {
+    let server = Server::start(
+        "redirects",
+        &[
+            "--redirect",
+            "/old=/fixture/",
+            "--redirect",
+            "/help.html=/fixture/",
+        ],
+    );
+
+    let redirect = server.get("/old");
+    assert_eq!(redirect.status, 302);
+    assert_eq!(redirect.header("location"), Some("/fixture/"));
+
+    // Over the files of the documentation too.
+    let redirect = server.get("/help.html");
+    assert_eq!(redirect.status, 302);
+    assert_eq!(redirect.header("location"), Some("/fixture/"));
+}
+
+#[test]
+fn redirects_permanently() {
+    let server = Server::start(
+        "redirects-permanent",
+        &["--redirect", "/old=/fixture/", "--permanent-redirects"],
+    );
+
+    let redirect = server.get("/old");
+    assert_eq!(redirect.status, 301);
+    assert_eq!(redirect.header("location"), Some("/fixture/"));
+}
+
+/// Runs the server with some arguments, expecting it to refuse them.
+///
+/// Arguments:
+/// * `args` - The arguments.
+///
+/// Returns:
+/// [`String`] -> What the server wrote to its error output.
+fn refused(args: &[&str]) -> String {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("redirects-refused");
+    copy_fixture(&root);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(&root)
+        .args(["--bind", "127.0.0.1:0"])
+        .args(args)
+        .stdout(Stdio::null())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn refuses_loops() {
+    let stderr = refused(&["--redirect", "/a=/b", "--redirect", "/b=/a"]);
+    assert!(stderr.contains("The redirect from `/a` loops back through `/a`!"));
+}
+
+#[test]
+fn refuses_reserved_paths() {
+    for path in [
+        "/__debug/config",
+        "/sitemap.xml",
+        "/__rebuild",
+        "/__reload",
+        "/guide/a",
+    ] {
+        let stderr = refused(&[
+            "--debug-endpoints",
+            "--sitemap",
+            "--public-url",
+            "https://docs.example.com",
+            "--redirect",
+            &format!("{path}=/foo/"),
+        ]);
+
+        assert!(stderr.contains(&format!(
+            "Could not redirect `{path}`, redirects must be from a path of the documentation!"
+        )));
+    }
+}
@@ -0,0 +1,77 @@
+//! Reloading pages served by the server when the documentation is rebuilt.
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{Server, WebSocket};
+use serde_json::Value;
+
+/// The opcode of WebSocket pings.
+const PING: u8 = 0x9;
+
+/// Reads the next text message of the reload WebSocket.
+///
+/// Arguments:
+/// * `websocket` - The connection.
+///
+/// Returns:
+/// [`Value`] -> The message.
+fn message(websocket: &mut WebSocket) -> Value {
+    serde_json::from_str(&websocket.message()).unwrap()
+}
+
+#[test]
+fn reloads_pages_served_before_builds() {
+    let server = Server::start(
+        "reload",
+        &["--no-watch", "--allow-remote-rebuild", "--reload-ping", "1"],
+    );
+
+    let page = server.get("/fixture/index.html").body;
+    assert!(page.contains(r#"<script data-path="/__reload" data-generation=""#));
+
+    let generation: u64 = page
+        .split_once(r#"data-generation=""#)
+        .and_then(|(_, rest)| rest.split_once('"'))
+        .and_then(|(generation, _)| generation.parse().ok())
+        .unwrap();
+
+    let mut websocket = server.websocket();
+    let hello = message(&mut websocket);
+    assert_eq!(hello["type"], "hello");
+    assert_eq!(hello["generation"], generation);
+
+    // Pinged on the interval of `--reload-ping`, rather than left idle.
+    let mut pinged = None;
+
+    for _ in 0..2 {
+        while websocket.frame().0 != PING {}
+
+        let now = Instant::now();
+
+        if let Some(last) = pinged.replace(now) {
+            let interval = now.duration_since(last);
+            assert!(interval > Duration::from_millis(500) && interval < Duration::from_secs(2));
+        }
+    }
+
+    assert_eq!(server.request("POST", "/__rebuild", &[]).status, 200);
+
+    // The initial build's reload can still be on its way, for the generation the page has.
+    let mut reload = message(&mut websocket);
+
+    while reload["generation"] == generation {
+        reload = message(&mut websocket);
+    }
+
+    assert_eq!(reload["type"], "reload");
+
+    // A page reconnecting after the build is told about it, so that it reloads even though it
+    // missed the reload.
+    let mut reconnected = server.websocket();
+    let hello = message(&mut reconnected);
+    assert_eq!(hello["type"], "hello");
+    assert_eq!(hello["generation"], reload["generation"]);
+    assert_ne!(hello["generation"], generation);
+}
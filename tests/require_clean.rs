@@ -0,0 +1,94 @@
+//! Refusing to build with uncommitted changes with `--require-clean`.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use common::{Server, copy_fixture};
+
+/// Runs git in a directory, checking that it succeeds.
+///
+/// Arguments:
+/// * `root` - The directory to run git at.
+/// * `args` - The arguments to pass to git.
+fn git(root: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args([
+            "-c",
+            "user.name=Tests",
+            "-c",
+            "user.email=tests@example.com",
+        ])
+        .args(args)
+        .current_dir(root)
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+}
+
+#[test]
+fn require_clean() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("require-clean");
+    copy_fixture(&root);
+    fs::remove_dir_all(root.join(".git")).ok();
+    fs::remove_file(root.join("uncommitted.txt")).ok();
+    fs::write(root.join(".gitignore"), "/target\n/Cargo.lock\n").unwrap();
+
+    git(&root, &["init", "--quiet"]);
+    git(&root, &["add", "."]);
+    git(&root, &["commit", "--quiet", "--message", "Fixture"]);
+
+    let server = Server::start("require-clean", &["--require-clean"]);
+    assert_eq!(server.get("/fixture/index.html").status, 200);
+    drop(server);
+
+    fs::write(root.join("uncommitted.txt"), "").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(&root)
+        .args(["--bind", "127.0.0.1:0", "--require-clean"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("uncommitted.txt"));
+}
+
+#[test]
+fn warns_outside_of_repositories() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("require-clean-no-repo");
+    copy_fixture(&root);
+
+    // The tests' directory is in the server's own repository, which git would find otherwise.
+    let output = Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(&root)
+        .args(["--bind", "127.0.0.1:0", "--require-clean"])
+        .args(["--example", "missing"])
+        .env("GIT_CEILING_DIRECTORIES", env!("CARGO_TARGET_TMPDIR"))
+        .output()
+        .unwrap();
+
+    // The missing example is only looked for once the repository was checked.
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("isn't in a git repository"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No example named `missing`"));
+}
+
+#[test]
+fn refuses_without_git() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("require-clean-no-git");
+    copy_fixture(&root);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(&root)
+        .args(["--bind", "127.0.0.1:0", "--require-clean"])
+        .env("PATH", "")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Could not run git"));
+}
@@ -0,0 +1,25 @@
+//! Serving a given page at `/` instead of redirecting to the documentation.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::Server;
+
+#[test]
+fn serves_root_page() {
+    let page = Path::new(env!("CARGO_TARGET_TMPDIR")).join("root-page.html");
+    fs::write(&page, "<!DOCTYPE html>\n<h1>Welcome</h1>\n").unwrap();
+
+    let server = Server::start("root-page", &["--root-page", page.to_str().unwrap()]);
+
+    let root = server.get("/");
+    assert_eq!(root.status, 200);
+    assert!(root.body.contains("<h1>Welcome</h1>"));
+    assert_eq!(server.get("/fixture/index.html").status, 200);
+
+    // It's read again on every request.
+    fs::write(&page, "<!DOCTYPE html>\n<h1>Edited</h1>\n").unwrap();
+    assert!(server.get("/").body.contains("<h1>Edited</h1>"));
+}
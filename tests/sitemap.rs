@@ -0,0 +1,29 @@
+//! The sitemap listing every page of the documentation.
+
+mod common;
+
+use common::Server;
+
+#[test]
+fn sitemap() {
+    let server = Server::start(
+        "sitemap",
+        &["--sitemap", "--public-url", "https://docs.example/crate/"],
+    );
+
+    let sitemap = server.get("/sitemap.xml");
+    assert_eq!(sitemap.status, 200);
+    assert_eq!(sitemap.header("content-type"), Some("application/xml"));
+    assert!(sitemap.body.starts_with("<?xml"));
+    assert!(
+        sitemap
+            .body
+            .contains("<loc>https://docs.example/crate/fixture/index.html</loc>")
+    );
+    assert!(
+        sitemap
+            .body
+            .contains("<loc>https://docs.example/crate/fixture/struct.Item.html</loc>")
+    );
+    assert!(!sitemap.body.contains("static.files"));
+}
@@ -0,0 +1,121 @@
+//! Building to a target directory other than cargo's.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::{env, fs};
+
+use common::{Server, copy_fixture, wait_until};
+use serde_json::Value;
+
+#[test]
+fn target_dir() {
+    let target = env::temp_dir().join("docs-server-target-dir-test");
+    let server = Server::start("target-dir", &["--target-dir", target.to_str().unwrap()]);
+
+    assert!(target.join("doc/fixture/index.html").is_file());
+    assert_eq!(server.get("/fixture/index.html").status, 200);
+}
+
+#[cfg(unix)]
+#[test]
+fn tmp_target() {
+    let mut server = Server::start("tmp-target", &["--tmp-target", "--debug-endpoints"]);
+
+    let config: Value = serde_json::from_str(&server.get("/__debug/config").body).unwrap();
+    let target = PathBuf::from(config["target_dir"].as_str().unwrap());
+    assert!(target.starts_with(env::temp_dir()));
+
+    assert!(target.join("doc/fixture/index.html").is_file());
+    assert_eq!(server.get("/fixture/index.html").status, 200);
+
+    server.terminate();
+    assert!(!target.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn tmp_target_removed_during_fail_fast_build() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("tmp-target-fail-fast");
+    copy_fixture(&root);
+    fs::remove_file(root.join("pre-build-started")).ok();
+
+    let tmp = root.join("tmp");
+    fs::remove_dir_all(&tmp).ok();
+    fs::create_dir_all(&tmp).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_docs"))
+        .current_dir(&root)
+        .args([
+            "--bind",
+            "127.0.0.1:0",
+            "--quiet",
+            "--tmp-target",
+            "--fail-fast",
+        ])
+        .args(["--pre-build", "touch pre-build-started; sleep 15"])
+        .env("TMPDIR", &tmp)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let target = tmp.join(format!("docs-server-{}-0", child.id()));
+    wait_until("the pre-build command started", || {
+        root.join("pre-build-started").exists()
+    });
+    assert!(target.is_dir());
+
+    Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+
+    assert!(child.wait().unwrap().success());
+    assert!(!target.exists());
+}
+
+#[test]
+fn warns_when_rebuilt_elsewhere() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("target-dir-elsewhere");
+    fs::remove_dir_all(root.join(".cargo")).ok();
+
+    let server = Server::start_logged(
+        "target-dir-elsewhere",
+        &[
+            "--target-dir",
+            "docs-target",
+            "--no-watch",
+            "--allow-remote-rebuild",
+        ],
+    );
+    assert!(!server.output().contains("but it's served from"));
+
+    // Building for the host's triple writes the documentation under it, like a configured
+    // `build.target` does, from then on.
+    let rustc = Command::new("rustc").arg("-vV").output().unwrap();
+    let rustc = String::from_utf8(rustc.stdout).unwrap();
+    let host = rustc
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .unwrap();
+
+    fs::create_dir_all(root.join(".cargo")).unwrap();
+    fs::write(
+        root.join(".cargo/config.toml"),
+        format!("[build]\ntarget = \"{host}\"\n"),
+    )
+    .unwrap();
+    fs::remove_dir_all(root.join("docs-target/doc/fixture")).unwrap();
+
+    assert_eq!(server.request("POST", "/__rebuild", &[]).status, 200);
+
+    let written = root.join("docs-target").join(host).join("doc");
+    wait_until("the rebuild warns", || {
+        server.output().contains(&format!(
+            "The documentation was built to `{}`, but it's served from",
+            written.display()
+        ))
+    });
+}
@@ -0,0 +1,29 @@
+//! Showing whether the last build succeeded in the terminal's title with `--terminal-status`.
+
+mod common;
+
+use std::fs;
+
+use common::Server;
+
+#[test]
+fn leaves_pipes_alone() {
+    let server = Server::start(
+        "terminal-status",
+        &[
+            "--terminal-status",
+            "--terminal-bell",
+            "--allow-remote-rebuild",
+        ],
+    );
+
+    // A failed rebuild would ring the bell too.
+    let lib = server.root.join("src/lib.rs");
+    let source = fs::read_to_string(&lib).unwrap();
+    fs::write(&lib, format!("{source}\npub struct Broken\n")).unwrap();
+    assert_eq!(server.request("POST", "/__rebuild", &[]).status, 500);
+
+    let output = server.output();
+    assert!(!output.contains("\x1b]0;"));
+    assert!(!output.contains('\x07'));
+}
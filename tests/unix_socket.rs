@@ -0,0 +1,33 @@
+//! Serving at a Unix socket alongside the TCP addresses.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+
+use common::Server;
+
+#[test]
+fn unix_socket() {
+    let socket = Path::new(env!("CARGO_TARGET_TMPDIR")).join("docs.sock");
+    let mut server = Server::start("unix-socket", &["--unix-socket", socket.to_str().unwrap()]);
+
+    let mut stream = UnixStream::connect(&socket).unwrap();
+    stream
+        .write_all(
+            b"GET /fixture/index.html HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        )
+        .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"));
+
+    assert_eq!(server.get("/fixture/index.html").status, 200);
+
+    server.terminate();
+    assert!(!socket.exists());
+}
@@ -0,0 +1,292 @@
+//! Rebuilding the documentation when the sources change.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use common::{Server, builds, wait_until};
+
+/// Starts a server writing its builds to a build log, removing the log of earlier runs first.
+///
+/// Arguments:
+/// * `name` - The name of the copy of the fixture, unique to the test.
+/// * `args` - Extra arguments to pass to the server.
+///
+/// Returns:
+/// [`Server`] -> The running server.
+fn start(name: &str, args: &[&str]) -> Server {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::remove_file(root.join("builds.jsonl")).ok();
+
+    let mut args = args.to_vec();
+    args.extend(["--build-log", "builds.jsonl"]);
+
+    Server::start(name, &args)
+}
+
+/// Adds an item to the fixture's sources.
+///
+/// Arguments:
+/// * `server` - The server documenting the fixture.
+/// * `name` - The name of the item.
+fn add_item(server: &Server, name: &str) {
+    let lib = server.root.join("src/lib.rs");
+    let source = fs::read_to_string(&lib).unwrap();
+    fs::write(
+        &lib,
+        format!("{source}\n/// Added later.\npub struct {name};\n"),
+    )
+    .unwrap();
+}
+
+#[test]
+fn rebuilds_on_changes() {
+    let server = start("watcher-rebuild", &[]);
+    assert_eq!(server.get("/fixture/struct.Added.html").status, 404);
+
+    add_item(&server, "Added");
+
+    wait_until("the new item is served", || {
+        server.get("/fixture/struct.Added.html").status == 200
+    });
+
+    let log = server.root.join("builds.jsonl");
+    wait_until("the rebuild is logged", || builds(&log) == 2);
+
+    let index = server.get("/fixture/index.html");
+    assert!(index.body.contains("struct.Added.html"));
+}
+
+#[test]
+fn debounces_changes() {
+    let server = start("watcher-debounce", &["--debounce", "1500"]);
+    let log = server.root.join("builds.jsonl");
+
+    // Every change comes before the debounce passes since the last one.
+    for name in ["First", "Second", "Third"] {
+        add_item(&server, name);
+        thread::sleep(Duration::from_millis(200));
+    }
+
+    wait_until("the changes are rebuilt", || {
+        server.get("/fixture/struct.Third.html").status == 200
+    });
+
+    thread::sleep(Duration::from_secs(2));
+    assert_eq!(builds(&log), 2);
+}
+
+#[test]
+fn ignores_target_directory() {
+    // Inside the watched sources, so that builds are told about what they write.
+    let server = start("watcher-target", &["--target-dir", "src/docs-target"]);
+    let log = server.root.join("builds.jsonl");
+
+    fs::write(server.root.join("src/docs-target/doc/written.html"), "").unwrap();
+    fs::create_dir_all(server.root.join("src/docs-target/debug")).unwrap();
+    fs::write(server.root.join("src/docs-target/debug/written"), "").unwrap();
+
+    thread::sleep(Duration::from_secs(2));
+    assert_eq!(builds(&log), 1);
+}
+
+#[test]
+fn waits_for_quiet_sources() {
+    let server = start("watcher-warmup", &["--warmup-quiet", "1500"]);
+    let log = server.root.join("builds.jsonl");
+
+    // Each change comes before the sources were quiet for long enough, so none is rebuilt.
+    for name in ["First", "Second", "Third"] {
+        add_item(&server, name);
+        thread::sleep(Duration::from_millis(500));
+    }
+
+    thread::sleep(Duration::from_secs(2));
+    assert_eq!(builds(&log), 1);
+
+    add_item(&server, "Added");
+
+    wait_until("the change after the warmup is rebuilt", || {
+        server.get("/fixture/struct.Added.html").status == 200
+    });
+    assert!(
+        server
+            .get("/fixture/index.html")
+            .body
+            .contains("struct.Third.html")
+    );
+}
+
+#[test]
+fn rebuilds_on_local_dependency_changes() {
+    let server = start("watcher-doc-dep", &["--doc-dep", "dep"]);
+    assert_eq!(server.get("/dep/struct.DepItem.html").status, 200);
+
+    let lib = server.root.join("dep/src/lib.rs");
+    let source = fs::read_to_string(&lib).unwrap();
+    fs::write(
+        &lib,
+        format!("{source}\n/// Added later.\npub struct Added;\n"),
+    )
+    .unwrap();
+
+    wait_until("the dependency's new item is served", || {
+        server.get("/dep/struct.Added.html").status == 200
+    });
+}
+
+#[test]
+fn only_documents_changed_targets() {
+    let server = start("watcher-scope", &[]);
+    let log = server.root.join("builds.jsonl");
+    let bin_docs = server.root.join("target/doc/tool");
+
+    // cargo documents targets again if their documentation is missing, so the binary's only
+    // comes back once a rebuild documents it.
+    fs::remove_dir_all(&bin_docs).unwrap();
+
+    add_item(&server, "Added");
+
+    wait_until("the library is rebuilt", || builds(&log) == 2);
+    assert_eq!(server.get("/fixture/struct.Added.html").status, 200);
+    assert!(!bin_docs.exists());
+
+    let bin = server.root.join("src/bin/tool.rs");
+    let source = fs::read_to_string(&bin).unwrap();
+    fs::write(&bin, format!("{source}\n/// Added later.\nstruct Added;\n")).unwrap();
+
+    wait_until("the binary is rebuilt", || builds(&log) == 3);
+    assert!(bin_docs.join("struct.Added.html").is_file());
+}
+
+#[test]
+fn restyles_without_rebuilding() {
+    let css = Path::new(env!("CARGO_TARGET_TMPDIR")).join("custom.css");
+    fs::write(&css, "body { color: red; }\n").unwrap();
+
+    let server = start(
+        "watcher-custom-css",
+        &["--custom-css", css.to_str().unwrap()],
+    );
+    let log = server.root.join("builds.jsonl");
+
+    let page = server.get("/fixture/index.html").body;
+    assert!(page.contains(r#"<link rel="stylesheet" href="/__custom.css" data-custom-css>"#));
+    assert_eq!(server.get("/__custom.css").body, "body { color: red; }\n");
+
+    let mut websocket = server.websocket();
+    assert!(websocket.message().contains(r#""type":"hello""#));
+
+    // The initial build's reload can still be on its way, and isn't a rebuild, which the build
+    // log tells apart.
+    let mut message = || {
+        let mut message = websocket.message();
+
+        while message.contains(r#""type":"reload""#) {
+            message = websocket.message();
+        }
+
+        message
+    };
+
+    // Saved like editors do, by replacing the stylesheet.
+    let saved = css.with_extension("css.tmp");
+    fs::write(&saved, "body { color: blue; }\n").unwrap();
+    fs::rename(&saved, &css).unwrap();
+
+    assert_eq!(message(), r#"{"type":"css"}"#);
+    assert_eq!(server.get("/__custom.css").body, "body { color: blue; }\n");
+
+    // Saved again, after the stylesheet the directory first had was replaced.
+    fs::write(&saved, "body { color: green; }\n").unwrap();
+    fs::rename(&saved, &css).unwrap();
+
+    assert_eq!(message(), r#"{"type":"css"}"#);
+    thread::sleep(Duration::from_secs(1));
+    assert_eq!(builds(&log), 1);
+
+    // The sources are still watched.
+    add_item(&server, "Added");
+    wait_until("the change is rebuilt", || builds(&log) == 2);
+}
+
+#[cfg(unix)]
+#[test]
+fn rebuilds_through_symlinks() {
+    let server = Server::start_prepared("watcher-symlink", &[], |root| {
+        // A module shared with other crates, linked into the fixture's sources.
+        let shared = root.join("shared");
+        fs::create_dir_all(&shared).unwrap();
+        fs::write(shared.join("mod.rs"), "//! Shared between crates.\n").unwrap();
+        std::os::unix::fs::symlink("../shared", root.join("src/shared")).unwrap();
+
+        let lib = root.join("src/lib.rs");
+        let source = fs::read_to_string(&lib).unwrap();
+        fs::write(&lib, format!("{source}\npub mod shared;\n")).unwrap();
+    });
+
+    assert_eq!(server.get("/fixture/shared/index.html").status, 200);
+
+    fs::write(
+        server.root.join("shared/mod.rs"),
+        "//! Shared between crates.\n\n/// Added later.\npub struct Added;\n",
+    )
+    .unwrap();
+
+    wait_until("the linked module's new item is served", || {
+        server.get("/fixture/shared/struct.Added.html").status == 200
+    });
+}
+
+#[test]
+fn tells_comment_changes_apart() {
+    let server = start("watcher-comments", &[]);
+    let log = server.root.join("builds.jsonl");
+    let lib = server.root.join("src/lib.rs");
+
+    let source = fs::read_to_string(&lib).unwrap();
+    fs::write(
+        &lib,
+        source.replace("/// A documented item.", "/// A documented item, edited."),
+    )
+    .unwrap();
+    wait_until("the comment change is rebuilt", || builds(&log) == 2);
+
+    add_item(&server, "Added");
+    wait_until("the code change is rebuilt", || builds(&log) == 3);
+
+    let builds: Vec<serde_json::Value> = fs::read_to_string(&log)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert_eq!(builds[1]["comments_only"], true);
+    assert_eq!(builds[2]["comments_only"], false);
+    assert!(
+        server
+            .get("/fixture/struct.Item.html")
+            .body
+            .contains("A documented item, edited.")
+    );
+}
+
+#[test]
+fn hybrid_rebuilds_once() {
+    let server = start(
+        "watcher-hybrid",
+        &["--watch-mode", "hybrid", "--poll-interval", "300"],
+    );
+    let log = server.root.join("builds.jsonl");
+
+    add_item(&server, "Added");
+
+    wait_until("the change is rebuilt", || builds(&log) == 2);
+
+    // The polling watcher finds the same change after the other one reported it.
+    thread::sleep(Duration::from_secs(2));
+    assert_eq!(builds(&log), 2);
+}
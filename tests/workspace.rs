@@ -0,0 +1,77 @@
+//! Documenting every member of a workspace on its own with `--workspace`.
+
+mod common;
+
+use std::fs;
+
+use common::Server;
+use serde_json::Value;
+
+#[test]
+fn serves_members_that_built() {
+    let mut server = Server::start_prepared(
+        "workspace",
+        &[
+            "--workspace",
+            "--deny-warnings",
+            "--events-to-stdout",
+            "--no-watch",
+            "--allow-remote-rebuild",
+        ],
+        |root| {
+            let manifest = root.join("Cargo.toml");
+            let source = fs::read_to_string(&manifest).unwrap();
+            fs::write(
+                &manifest,
+                format!("{source}\n[workspace]\nmembers = [\"dep\"]\n"),
+            )
+            .unwrap();
+
+            // Only documenting the dependency fails, so the fixture still builds.
+            fs::write(
+                root.join("dep/src/lib.rs"),
+                "/// Links to [`Missing`].\npub struct DepItem;\n",
+            )
+            .unwrap();
+        },
+    );
+
+    let index = server.get("/");
+    assert_eq!(index.status, 200);
+    assert!(index.body.contains("<a href=\"fixture/index.html\">"));
+    assert!(
+        index
+            .body
+            .contains("<h2>dep <span class=\"kind\">failed</span></h2>")
+    );
+    assert!(!index.body.contains("dep/index.html"));
+    assert_eq!(server.get("/fixture/index.html").status, 200);
+
+    let finished: Value = server
+        .output()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .find(|event| event["event"] == "build_finished")
+        .unwrap();
+    assert_eq!(finished["success"], true);
+
+    let members = finished["members"].as_array().unwrap();
+    assert_eq!(members.len(), 2);
+
+    for member in members {
+        assert_eq!(member["success"], member["name"] == "fixture");
+        assert!(member["duration_ms"].is_u64());
+    }
+
+    fs::write(
+        server.root.join("dep/src/lib.rs"),
+        "//! Fixed.\n\n/// An item.\npub struct DepItem;\n",
+    )
+    .unwrap();
+    assert_eq!(server.request("POST", "/__rebuild", &[]).status, 200);
+
+    assert_eq!(server.get("/").status, 307);
+    assert_eq!(server.get("/dep/index.html").status, 200);
+
+    server.terminate();
+}